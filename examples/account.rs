@@ -0,0 +1,50 @@
+#![allow(dead_code)]
+use std::env;
+
+use anyhow::Context;
+use neolite::{account::Account, client::Client, config::Config, lite::Lite};
+
+async fn list(account: Account) -> anyhow::Result<()> {
+    let accounts = account.list().await?;
+    for a in accounts {
+        println!(
+            "id: {}, name: {}, status: {}, next due: {:?}",
+            a.id,
+            a.extra_details.name,
+            a.status.as_str(),
+            a.next_due
+        );
+    }
+    Ok(())
+}
+
+async fn get(account: Account, id: u32) -> anyhow::Result<()> {
+    let account = account.get(id).await?;
+    println!(
+        "id: {}, billing cycle: {}, recurring amount: {}",
+        account.id, account.billingcycle, account.recurring_amount
+    );
+    println!(
+        "last invoice: {}, status: {}, due: {:?}",
+        account.last_invoice.id, account.last_invoice.status, account.last_invoice.duedate
+    );
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    dotenvy::from_filename("./examples/.env")?;
+
+    let id = env::var("ACCOUNT_ID").context("ACCOUNT_ID env not found.")?;
+    let id: u32 = id.parse()?;
+
+    let config = Config::load()?;
+    let client = Client::new(config)?;
+    let account = Lite::new(client).account().await?;
+
+    // list(account).await?;
+    get(account, id).await?;
+
+    Ok(())
+}
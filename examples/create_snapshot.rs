@@ -2,7 +2,9 @@
 use std::env;
 
 use anyhow::Context;
-use neolite::{client::Client, config::Config, lite::Lite, snapshot::SnapshotOpts};
+use neolite::{
+    client::Client, config::Config, lite::Lite, plan::BillingCycle, snapshot::SnapshotOpts,
+};
 
 async fn create(client: Client, vm_id: u32) -> anyhow::Result<()> {
     let lite = Lite::new(client);
@@ -10,7 +12,7 @@ async fn create(client: Client, vm_id: u32) -> anyhow::Result<()> {
     // (1) Select preferred billing cycle
     let product = lite.plan().await?;
     let product_resource = product.get_vm(1538).await?;
-    let billing_resource = product_resource.get_billing("Monthly").await?;
+    let billing_resource = product_resource.get_billing(BillingCycle::Monthly).await?;
     println!(
         "::: Billing. label: {}, price: {}",
         billing_resource.label, billing_resource.price,
@@ -19,7 +21,7 @@ async fn create(client: Client, vm_id: u32) -> anyhow::Result<()> {
     // (2) Create a virtual machine snapshot
     let snapshot = lite.snapshot().await?;
     let opts = SnapshotOpts {
-        billing: billing_resource,
+        cycle: billing_resource.cycle,
         use_credit_card: false,
         promocode: None,
     };
@@ -52,7 +54,7 @@ async fn restore_with(client: Client) -> anyhow::Result<()> {
     );
 
     // (2) Select preferred billing cycle
-    let billing_resource = plan_resource.get_billing("Monthly").await?;
+    let billing_resource = plan_resource.get_billing(BillingCycle::Monthly).await?;
     println!(
         "::: Billing. label: {}, price: {}",
         billing_resource.label, billing_resource.price,
@@ -71,7 +73,7 @@ async fn restore_with(client: Client) -> anyhow::Result<()> {
     let opts = neolite::snapshot::RestoreVirtualMachineOptions {
         plan: plan_resource,
         keypair: keypair_resource,
-        billing: billing_resource,
+        cycle: billing_resource.cycle,
         use_credit_card: false,
         promocode: None,
     };
@@ -99,12 +101,10 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
     dotenvy::from_filename("./examples/.env")?;
 
-    let url = "https://api.portal.biznetgio.dev/v1/neolites".parse::<http::Uri>()?;
-    let token = env::var("TOKEN").context("TOKEN env not found.")?;
     let id = env::var("VM_ID").context("VM_ID env not found.")?;
     let id: u32 = id.parse()?;
 
-    let config = Config::new(url, &token);
+    let config = Config::load()?;
     let client = Client::new(config)?;
 
     create(client, id).await?;
@@ -1,22 +1,35 @@
-use std::env;
-
-use anyhow::Context;
-use neolite::{client::Client, config::Config, lite::Lite, vm::VirtualMachineOptions};
+use neolite::{
+    client::Client,
+    config::Config,
+    lite::Lite,
+    plan::{BillingCycle, VmRequirements},
+    vm::VirtualMachineOptions,
+    wait::WaitOptions,
+};
 
 async fn create(client: Client) -> anyhow::Result<()> {
     let lite = Lite::new(client);
 
     // (1) Select preferred plan
-    // let resource = plan.list().await?;
+    // let plan_resource = plan.get_vm(1538).await?;
     let plan = lite.plan().await?;
-    let plan_resource = plan.get_vm(1538).await?;
+    let requirements = VmRequirements::new(BillingCycle::Monthly)
+        .min_cores(1)
+        .min_memory(1024)
+        .ip_available(true);
+    let plan_resource = plan
+        .find_vm(&requirements)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No plan meets the requirements"))?;
     println!(
         "::: plan. id: {}, name: {}",
         plan_resource.id, plan_resource.name,
     );
 
     // (2) Select preferred billing cycle
-    let billing_resource = plan_resource.get_billing("Monthly").await?;
+    let billing_resource = plan_resource.get_billing(BillingCycle::Monthly).await?;
     println!(
         "::: Billing. label: {}, price: {}",
         billing_resource.label, billing_resource.price,
@@ -50,7 +63,7 @@ async fn create(client: Client) -> anyhow::Result<()> {
         plan: plan_resource,
         os: os_resource,
         keypair: keypair_resource,
-        billing: billing_resource,
+        cycle: billing_resource.cycle,
         use_credit_card: false,
         promocode: None,
     };
@@ -68,6 +81,15 @@ async fn create(client: Client) -> anyhow::Result<()> {
         billing_resource.account_id, billing_resource.order_id
     );
 
+    // (7) Wait until the virtual machine is provisioned
+    let vm_resource = billing_resource
+        .wait_for_vm(&WaitOptions::default())
+        .await?;
+    println!(
+        "::: NeoLite VM ready. id: {}, status: {}",
+        vm_resource.id, vm_resource.status
+    );
+
     Ok(())
 }
 
@@ -76,9 +98,7 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
     dotenvy::from_filename("./examples/.env")?;
 
-    let url = "https://api.portal.biznetgio.dev/v1/neolites".parse::<http::Uri>()?;
-    let token = env::var("TOKEN").context("TOKEN env not found.")?;
-    let config = Config::new(url, &token);
+    let config = Config::load()?;
 
     let client = Client::new(config)?;
     create(client).await?;
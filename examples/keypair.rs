@@ -25,6 +25,14 @@ async fn create(keypair: Keypair) -> anyhow::Result<()> {
     Ok(())
 }
 
+async fn import(keypair: Keypair) -> anyhow::Result<()> {
+    let path = env::var("PUBLIC_KEY_PATH").context("PUBLIC_KEY_PATH env not found.")?;
+    let public_key = std::fs::read_to_string(path)?;
+    let key = keypair.import("gandalf1", &public_key).await?;
+    println!("id: {}, name: {}", key.id, key.name);
+    Ok(())
+}
+
 async fn delete(keypair: Keypair, id: u32) -> anyhow::Result<()> {
     keypair.delete(id).await?;
     println!("::: Keypair deleted.");
@@ -37,17 +45,16 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
     dotenvy::from_filename("./examples/.env")?;
 
-    let url = "https://api.portal.biznetgio.dev/v1/neolites".parse::<http::Uri>()?;
-    let token = env::var("TOKEN").context("TOKEN env not found.")?;
     let id = env::var("KEYPAIR_ID").context("KEYPAIR_ID env not found.")?;
     let id: u32 = id.parse()?;
 
-    let config = Config::new(url, &token);
+    let config = Config::load()?;
     let client = Client::new(config)?;
     let keypair = Lite::new(client).keypair().await?;
 
     // list(keypair).await?;
     // create(keypair).await?;
+    // import(keypair).await?;
     get(keypair, id).await?;
     // delete(keypair, id).await?;
 
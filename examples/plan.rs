@@ -68,9 +68,7 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
     dotenvy::from_filename("./examples/.env")?;
 
-    let url = "https://api.portal.biznetgio.dev/v1/neolites".parse::<http::Uri>()?;
-    let token = env::var("TOKEN").context("TOKEN env not found.")?;
-    let config = Config::new(url, &token);
+    let config = Config::load()?;
     let client = Client::new(config)?;
     let id = env::var("PLAN_ID").context("PLAN_ID env not found.")?;
     let id: u32 = id.parse()?;
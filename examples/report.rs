@@ -0,0 +1,46 @@
+#![allow(dead_code)]
+use chrono::{Days, Local};
+use neolite::{client::Client, config::Config, lite::Lite, report::Report};
+
+async fn cost(report: Report) -> anyhow::Result<()> {
+    let cost = report.cost().await?;
+    println!("monthly total: {}", cost.total);
+    for (region, amount) in cost.by_region {
+        println!("region {}: {}", region, amount);
+    }
+    for (product, amount) in cost.by_product {
+        println!("product {}: {}", product, amount);
+    }
+    Ok(())
+}
+
+async fn renewals(report: Report) -> anyhow::Result<()> {
+    let today = Local::now().date_naive();
+    let until = today + Days::new(30);
+    for renewal in report.renewals(today, until).await? {
+        println!(
+            "{}: {} ({}) due {}, {}",
+            renewal.account_id,
+            renewal.name,
+            renewal.billingcycle,
+            renewal.next_due,
+            renewal.recurring_amount
+        );
+    }
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    env_logger::init();
+    dotenvy::from_filename("./examples/.env")?;
+
+    let config = Config::load()?;
+    let client = Client::new(config)?;
+    let report = Lite::new(client).report().await?;
+
+    cost(report).await?;
+    // renewals(report).await?;
+
+    Ok(())
+}
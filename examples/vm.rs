@@ -3,7 +3,11 @@ use std::env;
 
 use anyhow::Context;
 use neolite::{
-    client::Client, config::Config, lite::Lite, vm::VirtualMachine, vm::VirtualMachineStatus,
+    client::Client,
+    config::Config,
+    lite::Lite,
+    vm::{PowerState, VirtualMachine, VirtualMachineStatus},
+    wait::WaitOptions,
 };
 
 async fn list(vm: VirtualMachine) -> anyhow::Result<()> {
@@ -41,6 +45,8 @@ async fn delete(vm: VirtualMachine, id: u32) -> anyhow::Result<()> {
 async fn stop(vm: VirtualMachine, id: u32) -> anyhow::Result<()> {
     let vm = vm.get(id).await?;
     vm.stop().await?;
+    vm.wait_for_state(PowerState::Stopped, &WaitOptions::default())
+        .await?;
     println!("::: Virtual machine stopped.");
 
     Ok(())
@@ -51,12 +57,10 @@ async fn main() -> anyhow::Result<()> {
     env_logger::init();
     dotenvy::from_filename("./examples/.env")?;
 
-    let url = "https://api.portal.biznetgio.dev/v1/neolites".parse::<http::Uri>()?;
-    let token = env::var("TOKEN").context("TOKEN env not found.")?;
     let id = env::var("VM_ID").context("VM_ID env not found.")?;
     let id: u32 = id.parse()?;
 
-    let config = Config::new(url, &token);
+    let config = Config::load()?;
     let client = Client::new(config)?;
     let vm = Lite::new(client).vm().await?;
 
@@ -1,19 +1,76 @@
-use crate::config::Config;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use reqwest::{Method, StatusCode};
+use crate::{
+    config::Config,
+    error::ApiError,
+    redact,
+    retry::{self, RetryPolicy},
+    transport::{self, Player, Recorder, ReqwestTransport, Transport},
+};
+
+use reqwest::{
+    header::{HeaderMap, HeaderName, HeaderValue},
+    Method, StatusCode,
+};
+pub use reqwest::{Certificate, Proxy};
 use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_option_number_from_string;
 use serde_json as json;
+use tracing::Instrument;
 
-#[derive(Debug, Default)]
+#[derive(Debug)]
 pub struct Client {
     config: Config,
-    requester: reqwest::Client,
+    transport: Arc<dyn Transport>,
+    retry: RetryPolicy,
+}
+
+/// A client with the default [`ClientBuilder`] settings and an empty [`Config`].
+///
+/// # Panics
+///
+/// Like [`reqwest::Client::new`], if the TLS backend fails to initialize.
+impl Default for Client {
+    fn default() -> Self {
+        Self::builder(Config::default())
+            .build()
+            .expect("the default client settings are valid")
+    }
 }
 
 impl Client {
+    /// Creates a client with the default [`ClientBuilder`] settings.
     pub fn new(config: Config) -> Result<Self, crate::Error> {
-        let requester = reqwest::Client::builder().build()?;
-        Ok(Self { config, requester })
+        Self::builder(config).build()
+    }
+    pub fn builder(config: Config) -> ClientBuilder {
+        ClientBuilder::new(config)
+    }
+    /// Creates a client talking to the portal that writes every exchange to
+    /// the cassette at `path`.
+    pub fn record(config: Config, path: impl Into<PathBuf>) -> Result<Self, crate::Error> {
+        Self::builder(config).record(path).build()
+    }
+    /// Creates a client answering from the cassette at `path` instead of the portal.
+    pub fn replay(config: Config, path: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        Ok(Self::with_transport(config, Player::from_file(path)?))
+    }
+    /// Creates a client that sends its requests through `transport`.
+    pub fn with_transport(config: Config, transport: impl Transport + 'static) -> Self {
+        Self {
+            config,
+            transport: Arc::new(transport),
+            retry: RetryPolicy::default(),
+        }
+    }
+    /// Replaces the default [`RetryPolicy`].
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
     }
     pub async fn get(&self, path: &str) -> Result<json::Value, crate::Error> {
         self.send(Method::GET, path, None).await
@@ -34,59 +91,721 @@ impl Client {
     pub async fn delete(&self, path: &str) -> Result<json::Value, crate::Error> {
         self.send(Method::DELETE, path, None).await
     }
+    /// Sends a request that places an order or otherwise must not run twice.
+    ///
+    /// Like `POST`, it is only retried when
+    /// [`RetryPolicy::retry_non_idempotent`] is set, whatever its verb.
+    pub(crate) async fn send_once(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<json::Value>,
+    ) -> Result<json::Value, crate::Error> {
+        let (data, _meta) = self.request_with(method, path, body, false).await?;
+        Ok(data)
+    }
     async fn send(
         &self,
-        method: reqwest::Method,
+        method: Method,
         path: &str,
         body: Option<json::Value>,
     ) -> Result<json::Value, crate::Error> {
-        let url = format!("{}{}", self.config.base_url, path);
-        log::debug!("URL: {:?}", url);
+        let (data, _meta) = self.request(method, path, body).await?;
+        Ok(data)
+    }
+    /// Sends a request and returns the `data` of the response envelope
+    /// together with its metadata.
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<json::Value>,
+    ) -> Result<(json::Value, ResponseMeta), crate::Error> {
+        let idempotent = retry::is_idempotent(&method);
+        self.request_with(method, path, body, idempotent).await
+    }
+    async fn request_with(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<json::Value>,
+        idempotent: bool,
+    ) -> Result<(json::Value, ResponseMeta), crate::Error> {
+        let (template, account_id) = path_template(path);
+        let span = tracing::info_span!(
+            "neolite.request",
+            method = %method,
+            path = %template,
+            account_id = tracing::field::Empty,
+            status = tracing::field::Empty,
+            latency_ms = tracing::field::Empty,
+            retries = tracing::field::Empty,
+        );
+        if let Some(account_id) = account_id {
+            span.record("account_id", account_id);
+        }
+        self.send_with_retry(method, path, body, idempotent)
+            .instrument(span)
+            .await
+    }
+    async fn send_with_retry(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<json::Value>,
+        idempotent: bool,
+    ) -> Result<(json::Value, ResponseMeta), crate::Error> {
+        let base_url = self.config.base_url.to_string();
+        let url = format!("{}{}", base_url.trim_end_matches('/'), path);
+        tracing::debug!("URL: {:?}", url);
 
-        let response = self
-            .requester
-            .request(method, url)
-            .header("x-token", &self.config.token)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await?;
-        let status = response.status();
-        let text = response.text().await?;
-        log::trace!("Response: {:?}", &text);
-
-        match status {
-            StatusCode::OK | StatusCode::CREATED => {
-                let response: json::Value = json::from_str(&text)?;
-                match response.get("data") {
-                    Some(_) => {
-                        let response: Response = json::from_str(&text)?;
-                        Ok(response.data.to_owned())
-                    }
-                    None => {
-                        log::error!("status: {}, body: {:?}", status, text);
-                        Err(crate::Error::InvalidArgument(format!(
-                            "Incomplete response. Status: {}. Body: {}",
-                            status, text
-                        )))
-                    }
+        let span = tracing::Span::current();
+        let started = Instant::now();
+        let mut attempt = 1;
+        loop {
+            let result = match self.execute(&method, &url, &body).await {
+                Ok(response) => {
+                    let (parts, text) = response.into_parts();
+                    span.record("status", parts.status.as_u16());
+                    tracing::trace!("Response: {:?}", redact::body(&text));
+                    Self::parse(&method, path, parts.status, &parts.headers, text)
                 }
+                Err(e) => Err(e),
+            };
+            let error = match result {
+                Err(e) if e.is_retryable() && self.retry.can_retry(idempotent, attempt) => e,
+                result => {
+                    span.record("latency_ms", started.elapsed().as_millis() as u64);
+                    span.record("retries", attempt - 1);
+                    return result;
+                }
+            };
+            let Some(delay) = self.retry.delay(attempt, &error) else {
+                span.record("latency_ms", started.elapsed().as_millis() as u64);
+                span.record("retries", attempt - 1);
+                return Err(error);
+            };
+            tracing::warn!(
+                "attempt {} failed: {}. Retrying in {:?}",
+                attempt,
+                error,
+                delay
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+    async fn execute(
+        &self,
+        method: &Method,
+        url: &str,
+        body: &Option<json::Value>,
+    ) -> Result<transport::Response, crate::Error> {
+        // Sensitive values are left out of `Debug` output and HTTP/2 header compression.
+        let mut token = HeaderValue::from_str(self.config.token.expose()).map_err(|_| {
+            crate::Error::InvalidArgument("The token is not a valid header value".to_string())
+        })?;
+        token.set_sensitive(true);
+        let request = http::Request::builder()
+            .method(method.clone())
+            .uri(url)
+            .header("x-token", token)
+            .header("Content-Type", "application/json")
+            .body(body.clone())
+            .map_err(|e| crate::Error::InvalidArgument(format!("Invalid request: {}", e)))?;
+        self.transport.send(request).await
+    }
+    fn parse(
+        method: &Method,
+        path: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        text: String,
+    ) -> Result<(json::Value, ResponseMeta), crate::Error> {
+        let envelope = json::from_str::<Envelope>(&text);
+        let (code, message) = match &envelope {
+            Ok(envelope) => (envelope.code, envelope.message.clone()),
+            Err(_) => (None, None),
+        };
+        let failure = |status: StatusCode, text: String| {
+            let body = redact::body(&text);
+            tracing::error!("status: {}, body: {:?}", status, body);
+            crate::Error::from_response(ApiError {
+                status,
+                method: method.clone(),
+                path: path.to_string(),
+                code,
+                message: message.clone(),
+                body,
+                retry_after: retry::parse_retry_after(headers),
+            })
+        };
+
+        if !status.is_success() {
+            return Err(failure(status, text));
+        }
+        let envelope = envelope.map_err(|e| crate::Error::Decode {
+            source: e,
+            body: Some(redact::body(&text)),
+        })?;
+        if envelope.success == Some(false) {
+            // The API sometimes reports failures with `200 OK`. Classify them
+            // by the envelope code when it looks like an HTTP status.
+            let status = code
+                .and_then(|code| u16::try_from(code).ok())
+                .and_then(|code| StatusCode::from_u16(code).ok())
+                .filter(|code| code.is_client_error() || code.is_server_error())
+                .unwrap_or(status);
+            return Err(failure(status, text));
+        }
+        match envelope.data {
+            Some(data) => Ok((data, ResponseMeta { code, message })),
+            None => {
+                let source = <json::Error as serde::de::Error>::missing_field("data");
+                Err(crate::Error::Decode {
+                    source,
+                    body: Some(redact::body(&text)),
+                })
             }
-            StatusCode::NOT_FOUND => Err(crate::Error::NotFound("Resource not found".into())),
-            _ => {
-                log::error!("status: {}, body: {:?}", status, text);
-                Err(crate::Error::InvalidArgument(format!(
-                    "Request failed. Status: {}. Body: {}",
-                    status, text
-                )))
+        }
+    }
+}
+
+/// Replaces ids in `path` with `{id}` so calls group well in traces,
+/// e.g. `/accounts/{id}/vm-details`.
+///
+/// Also returns the account id of account, VM and snapshot routes, which all
+/// address an account. Other ids, like the product id of `/products/{id}/oss`,
+/// are not returned.
+fn path_template(path: &str) -> (String, Option<u32>) {
+    let path = path.split('?').next().unwrap_or_default();
+    let mut account_id = None;
+    let mut previous = "";
+    let mut segments = Vec::new();
+    for segment in path.split('/') {
+        match segment.parse::<u32>() {
+            Ok(value) => {
+                if matches!(previous, "accounts" | "snapshots") {
+                    account_id = account_id.or(Some(value));
+                }
+                segments.push("{id}");
             }
+            Err(_) => segments.push(segment),
         }
+        previous = segment;
+    }
+    (segments.join("/"), account_id)
+}
+
+pub const DEFAULT_USER_AGENT: &str = concat!("neolite/", env!("CARGO_PKG_VERSION"));
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Configures the HTTP side of a [`Client`].
+///
+/// ```no_run
+/// # fn run(config: neolite::config::Config) -> Result<(), neolite::Error> {
+/// use std::time::Duration;
+/// use neolite::client::Client;
+///
+/// let client = Client::builder(config)
+///     .timeout(Duration::from_secs(10))
+///     .user_agent("provisioner/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ClientBuilder {
+    config: Config,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxies: Vec<Proxy>,
+    no_proxy: bool,
+    user_agent: String,
+    root_certificates: Vec<Certificate>,
+    default_headers: HeaderMap,
+    http2_prior_knowledge: bool,
+    pool_idle_timeout: Option<Duration>,
+    pool_max_idle_per_host: Option<usize>,
+    retry: RetryPolicy,
+    record: Option<PathBuf>,
+    transport: Option<Arc<dyn Transport>>,
+}
+
+impl ClientBuilder {
+    pub fn new(config: Config) -> Self {
+        Self {
+            config,
+            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: Some(DEFAULT_CONNECT_TIMEOUT),
+            proxies: Vec::new(),
+            no_proxy: false,
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            root_certificates: Vec::new(),
+            default_headers: HeaderMap::new(),
+            http2_prior_knowledge: false,
+            pool_idle_timeout: None,
+            pool_max_idle_per_host: None,
+            retry: RetryPolicy::default(),
+            record: None,
+            transport: None,
+        }
+    }
+    /// Total time allowed for a single attempt. `None` disables it.
+    pub fn timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.timeout = timeout.into();
+        self
+    }
+    pub fn connect_timeout(mut self, timeout: impl Into<Option<Duration>>) -> Self {
+        self.connect_timeout = timeout.into();
+        self
+    }
+    /// Routes requests through `proxy`. Without it, the `HTTP(S)_PROXY`
+    /// environment variables are honoured.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+    /// Ignores proxies, including the ones from the environment.
+    pub fn no_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+    /// Trusts `certificate` in addition to the bundled root certificates.
+    pub fn add_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+    /// Sent with every request. `x-token` is always set from the [`Config`].
+    pub fn default_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.default_headers.insert(name, value);
+        self
+    }
+    pub fn default_headers(mut self, headers: HeaderMap) -> Self {
+        self.default_headers.extend(headers);
+        self
+    }
+    pub fn http2_prior_knowledge(mut self) -> Self {
+        self.http2_prior_knowledge = true;
+        self
+    }
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+    /// Writes every exchange to the cassette at `path`.
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.record = Some(path.into());
+        self
+    }
+    /// Sends requests through `transport`. The HTTP settings are then ignored.
+    pub fn transport(mut self, transport: impl Transport + 'static) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+    pub fn build(self) -> Result<Client, crate::Error> {
+        let transport = match self.transport {
+            Some(transport) => transport,
+            None => {
+                let mut builder = reqwest::Client::builder()
+                    .user_agent(self.user_agent)
+                    .default_headers(self.default_headers);
+                if let Some(timeout) = self.timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if self.no_proxy {
+                    builder = builder.no_proxy();
+                }
+                for proxy in self.proxies {
+                    builder = builder.proxy(proxy);
+                }
+                for certificate in self.root_certificates {
+                    builder = builder.add_root_certificate(certificate);
+                }
+                if self.http2_prior_knowledge {
+                    builder = builder.http2_prior_knowledge();
+                }
+                if let Some(timeout) = self.pool_idle_timeout {
+                    builder = builder.pool_idle_timeout(timeout);
+                }
+                if let Some(max) = self.pool_max_idle_per_host {
+                    builder = builder.pool_max_idle_per_host(max);
+                }
+                Arc::new(ReqwestTransport::new(builder.build()?))
+            }
+        };
+        let transport: Arc<dyn Transport> = match self.record {
+            Some(path) => Arc::new(Recorder::new(transport, path)),
+            None => transport,
+        };
+        Ok(Client {
+            config: self.config,
+            transport,
+            retry: self.retry,
+        })
     }
 }
 
+/// Metadata of the NEO Lite response envelope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResponseMeta {
+    pub code: Option<i32>,
+    pub message: Option<String>,
+}
+
+// Every NEO Lite response is wrapped in `{ success, code, message, data }`.
 #[derive(Debug, Deserialize, Serialize)]
-struct Response {
-    success: bool,
-    code: i32,
-    data: json::Value,
+struct Envelope {
+    success: Option<bool>,
+    #[serde(default, deserialize_with = "deserialize_option_number_from_string")]
+    code: Option<i32>,
+    message: Option<String>,
+    // `data: null` is a valid payload for actions. Only a missing field is an error.
+    #[serde(default, deserialize_with = "deserialize_present")]
+    data: Option<json::Value>,
+}
+
+fn deserialize_present<'de, D>(deserializer: D) -> Result<Option<json::Value>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    json::Value::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+    use super::*;
+    use crate::transport::MemoryTransport;
+
+    const PATH: &str = "/accounts/1/vm-details";
+    const UNAVAILABLE: &str = r#"{"success": false, "message": "Service Unavailable"}"#;
+
+    fn client(transport: &Arc<MemoryTransport>, policy: RetryPolicy) -> Client {
+        let config = Config::new("http://neolite.test".parse().unwrap(), "token");
+        Client::with_transport(config, Arc::clone(transport)).with_retry_policy(policy)
+    }
+
+    fn policy() -> RetryPolicy {
+        RetryPolicy::new()
+            .max_attempts(3)
+            .base_delay(Duration::from_millis(1))
+            .jitter(false)
+    }
+
+    fn retry_after(seconds: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(seconds).unwrap());
+        headers
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let transport = Arc::new(MemoryTransport::new());
+        let unavailable = StatusCode::SERVICE_UNAVAILABLE;
+        transport.push(Method::GET, PATH, unavailable, UNAVAILABLE);
+        transport.push(Method::GET, PATH, unavailable, UNAVAILABLE);
+        transport.push_data(Method::GET, PATH, json::json!({ "id": 1 }));
+
+        let data = client(&transport, policy()).get(PATH).await.unwrap();
+        assert_eq!(data, json::json!({ "id": 1 }));
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_attempts() {
+        let transport = Arc::new(MemoryTransport::new());
+        transport.push(Method::GET, PATH, StatusCode::BAD_GATEWAY, UNAVAILABLE);
+
+        let error = client(&transport, policy()).get(PATH).await.unwrap_err();
+        assert!(matches!(error, crate::Error::Server(_)));
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_client_errors() {
+        let transport = Arc::new(MemoryTransport::new());
+        let body = r#"{"success": false, "message": "Invalid name"}"#;
+        transport.push(Method::GET, PATH, StatusCode::BAD_REQUEST, body);
+
+        let error = client(&transport, policy()).get(PATH).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::BAD_REQUEST));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn honours_retry_after() {
+        let transport = Arc::new(MemoryTransport::new());
+        let status = StatusCode::TOO_MANY_REQUESTS;
+        transport.push_with_headers(Method::GET, PATH, status, retry_after("1"), UNAVAILABLE);
+        transport.push_data(Method::GET, PATH, json::json!({ "id": 1 }));
+
+        let started = Instant::now();
+        client(&transport, policy()).get(PATH).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(1));
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_when_retry_after_exceeds_max_delay() {
+        let transport = Arc::new(MemoryTransport::new());
+        let status = StatusCode::TOO_MANY_REQUESTS;
+        transport.push_with_headers(Method::GET, PATH, status, retry_after("60"), UNAVAILABLE);
+        transport.push_data(Method::GET, PATH, json::json!({ "id": 1 }));
+
+        let policy = policy().max_delay(Duration::from_secs(5));
+        let error = client(&transport, policy).get(PATH).await.unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(60)));
+        assert_eq!(transport.requests().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_post_by_default() {
+        let path = "/keypairs";
+        let transport = Arc::new(MemoryTransport::new());
+        transport.push(Method::POST, path, StatusCode::BAD_GATEWAY, UNAVAILABLE);
+        transport.push(Method::POST, path, StatusCode::BAD_GATEWAY, UNAVAILABLE);
+        transport.push_data(Method::POST, path, json::json!({ "id": 1 }));
+
+        let result = client(&transport, policy())
+            .post(path, json::json!({}))
+            .await;
+        assert!(result.is_err());
+        assert_eq!(transport.requests().len(), 1);
+
+        let policy = policy().retry_non_idempotent(true);
+        let client = client(&transport, policy);
+        client.post(path, json::json!({})).await.unwrap();
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn errors_never_show_secrets() {
+        let transport = Arc::new(MemoryTransport::new());
+        let secret = r#"{"success": false, "data": {"console_password": "Mellon123"}}"#;
+        transport.push(Method::GET, PATH, StatusCode::BAD_REQUEST, secret);
+        let path = "/keypairs/1";
+        let secret = r#"{"success": true, "private_key": "-----BEGIN OPENSSH"}"#;
+        transport.push(Method::GET, path, StatusCode::OK, secret);
+
+        let client = client(&transport, policy());
+        for path in [PATH, path] {
+            let error = client.get(path).await.unwrap_err();
+            for text in [format!("{:?}", error), error.to_string()] {
+                assert!(!text.contains("Mellon123"), "{}", text);
+                assert!(!text.contains("BEGIN OPENSSH"), "{}", text);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_orders_sent_with_put() {
+        let path = "/accounts/1/storage";
+        let transport = Arc::new(MemoryTransport::new());
+        transport.push(Method::PUT, path, StatusCode::BAD_GATEWAY, UNAVAILABLE);
+        transport.push(Method::PUT, path, StatusCode::BAD_GATEWAY, UNAVAILABLE);
+        transport.push_data(Method::PUT, path, json::json!({ "id": 1 }));
+
+        let client = client(&transport, policy());
+        let body = Some(json::json!({ "disk_size": 40 }));
+        assert!(client.send_once(Method::PUT, path, body).await.is_err());
+        assert_eq!(transport.requests().len(), 1);
+
+        client.put_with_body(path, json::json!({})).await.unwrap();
+        assert_eq!(transport.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_snapshot_restores() {
+        let path = "/snapshots/accounts/7/restore";
+        let transport = Arc::new(MemoryTransport::new());
+        transport.push(Method::PUT, path, StatusCode::GATEWAY_TIMEOUT, UNAVAILABLE);
+        transport.push_data(Method::PUT, path, json::Value::Null);
+
+        let snapshot = crate::snapshot::Snapshot::new(Arc::new(client(&transport, policy())));
+        assert!(snapshot.restore(7).await.is_err());
+        assert_eq!(transport.requests().len(), 1);
+
+        let policy = policy().retry_non_idempotent(true);
+        let snapshot = crate::snapshot::Snapshot::new(Arc::new(client(&transport, policy)));
+        snapshot.restore(7).await.unwrap();
+        assert_eq!(transport.requests().len(), 2);
+    }
+
+    /// Serves a single connection on a local port, answering with `response`
+    /// or holding the connection open without answering. Returns the port
+    /// and the received request head.
+    fn serve_once(response: Option<&'static str>) -> (u16, std::thread::JoinHandle<String>) {
+        use std::io::{BufRead, BufReader, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut head = String::new();
+            while reader.read_line(&mut head).unwrap() > 2 {}
+            match response {
+                Some(body) => write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+                .unwrap(),
+                None => std::thread::sleep(Duration::from_secs(2)),
+            }
+            head.to_ascii_lowercase()
+        });
+        (port, handle)
+    }
+
+    fn config(url: &str) -> Config {
+        Config::new(url.parse().unwrap(), "token")
+    }
+
+    const OK: &str = r#"{"success": true, "data": []}"#;
+
+    #[tokio::test]
+    async fn builder_sets_the_user_agent_and_headers() {
+        let (port, server) = serve_once(Some(OK));
+        let client = Client::builder(config(&format!("http://127.0.0.1:{}/v1", port)))
+            .no_proxy()
+            .user_agent("provisioner/1.0")
+            .default_header(
+                HeaderName::from_static("x-request-source"),
+                HeaderValue::from_static("tests"),
+            )
+            .build()
+            .unwrap();
+
+        client.get("/keypairs").await.unwrap();
+        let head = server.join().unwrap();
+        assert!(head.starts_with("get /v1/keypairs http/1.1"), "{}", head);
+        assert!(head.contains("user-agent: provisioner/1.0\r\n"), "{}", head);
+        assert!(head.contains("x-request-source: tests\r\n"), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn builder_uses_the_default_user_agent() {
+        let (port, server) = serve_once(Some(OK));
+        let client = Client::builder(config(&format!("http://127.0.0.1:{}", port)))
+            .no_proxy()
+            .build()
+            .unwrap();
+
+        client.get("/keypairs").await.unwrap();
+        let head = server.join().unwrap();
+        let expected = format!("user-agent: {}\r\n", DEFAULT_USER_AGENT);
+        assert!(head.contains(&expected), "{}", head);
+    }
+
+    #[tokio::test]
+    async fn builder_applies_the_timeout() {
+        let (port, _server) = serve_once(None);
+        let client = Client::builder(config(&format!("http://127.0.0.1:{}", port)))
+            .no_proxy()
+            .timeout(Duration::from_millis(100))
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+
+        let started = Instant::now();
+        let error = client.get("/keypairs").await.unwrap_err();
+        assert!(matches!(error, crate::Error::Timeout(_)), "{:?}", error);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[tokio::test]
+    async fn builder_sends_requests_through_the_proxy() {
+        let (port, server) = serve_once(Some(OK));
+        let proxy = Proxy::http(format!("http://127.0.0.1:{}", port)).unwrap();
+        let client = Client::builder(config("http://neolite.test/v1/neolites"))
+            .proxy(proxy)
+            .build()
+            .unwrap();
+
+        client.get("/keypairs").await.unwrap();
+        let head = server.join().unwrap();
+        assert!(
+            head.starts_with("get http://neolite.test/v1/neolites/keypairs http/1.1"),
+            "{}",
+            head
+        );
+    }
+
+    #[tokio::test]
+    async fn marks_the_token_sensitive() {
+        #[derive(Debug, Default)]
+        struct Capture(std::sync::Mutex<Option<HeaderMap>>);
+
+        impl Transport for Capture {
+            fn send(
+                &self,
+                request: transport::Request,
+            ) -> transport::BoxFuture<'_, Result<transport::Response, crate::Error>> {
+                *self.0.lock().unwrap() = Some(request.headers().clone());
+                Box::pin(async { Ok(http::Response::new(OK.to_string())) })
+            }
+        }
+
+        let capture = Arc::new(Capture::default());
+        let client = Client::with_transport(config("http://neolite.test"), Arc::clone(&capture));
+        client.get("/keypairs").await.unwrap();
+
+        let headers = capture.0.lock().unwrap().take().unwrap();
+        assert!(headers["x-token"].is_sensitive());
+        assert_eq!(headers["x-token"], "token");
+        assert!(!format!("{:?}", headers).contains("\"token\""));
+    }
+
+    #[test]
+    fn templates_paths_and_keeps_only_account_ids() {
+        let cases = [
+            ("/accounts", "/accounts", None),
+            ("/accounts?status=Active", "/accounts", None),
+            ("/accounts/1001", "/accounts/{id}", Some(1001)),
+            (
+                "/accounts/1001/vm-state/2",
+                "/accounts/{id}/vm-state/{id}",
+                Some(1001),
+            ),
+            (
+                "/snapshots/accounts/1002/restore",
+                "/snapshots/accounts/{id}/restore",
+                Some(1002),
+            ),
+            ("/snapshots/1002", "/snapshots/{id}", Some(1002)),
+            ("/products/1538/oss", "/products/{id}/oss", None),
+            ("/snapshots/products/7", "/snapshots/products/{id}", None),
+            ("/keypairs/3", "/keypairs/{id}", None),
+        ];
+        for (path, template, account_id) in cases {
+            assert_eq!(
+                path_template(path),
+                (template.to_string(), account_id),
+                "{}",
+                path
+            );
+        }
+    }
 }
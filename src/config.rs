@@ -1,14 +1,250 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::secret::Secret;
+
+pub const DEFAULT_BASE_URL: &str = "https://api.portal.biznetgio.dev/v1/neolites";
+pub const DEFAULT_PROFILE: &str = "default";
+
+pub const ENV_URL: &str = "NEOLITE_URL";
+pub const ENV_TOKEN: &str = "NEOLITE_TOKEN";
+pub const ENV_PROFILE: &str = "NEOLITE_PROFILE";
+pub const ENV_CONFIG_FILE: &str = "NEOLITE_CONFIG_FILE";
+
 #[derive(Debug, Default)]
 pub struct Config {
     pub base_url: http::Uri,
-    pub token: String,
+    pub token: Secret,
 }
 
 impl Config {
     pub fn new(url: http::Uri, token: &str) -> Self {
         Self {
             base_url: url,
-            token: token.to_string(),
+            token: Secret::from(token),
+        }
+    }
+    /// Reads `NEOLITE_TOKEN` and the optional `NEOLITE_URL`.
+    pub fn from_env() -> Result<Self, crate::Error> {
+        let token = env_var(ENV_TOKEN)
+            .map(Secret::from)
+            .ok_or_else(|| crate::Error::Config(format!("`{}` is not set", ENV_TOKEN)))?;
+        let url = env_var(ENV_URL).unwrap_or_else(|| DEFAULT_BASE_URL.to_string());
+        Ok(Self {
+            base_url: parse_url(&url)?,
+            token,
+        })
+    }
+    /// Reads `profile` from a config file. Without a profile name, the file's
+    /// `default_profile` is used, then `default`.
+    pub fn from_file(path: impl AsRef<Path>, profile: Option<&str>) -> Result<Self, crate::Error> {
+        let file = ConfigFile::read(path.as_ref())?;
+        let name = profile
+            .or(file.default_profile.as_deref())
+            .unwrap_or(DEFAULT_PROFILE);
+        file.config(name)
+    }
+    /// Loads the configuration the way every NEO Lite tool should.
+    ///
+    /// The config file is read from `NEOLITE_CONFIG_FILE` or
+    /// `$XDG_CONFIG_HOME/neolite/config.toml` (`~/.config/neolite/config.toml`).
+    ///
+    /// The URL and the token are always taken together from one source, so
+    /// a token of one portal is never sent to the URL of another:
+    ///
+    /// 1. The profile named by `NEOLITE_PROFILE`. `NEOLITE_URL` and
+    ///    `NEOLITE_TOKEN` are ignored.
+    /// 2. The `NEOLITE_TOKEN` and `NEOLITE_URL` environment variables.
+    ///    `NEOLITE_URL` without `NEOLITE_TOKEN` is an error.
+    /// 3. The file's `default_profile`, or the `default` profile.
+    ///
+    /// A missing URL falls back to [`DEFAULT_BASE_URL`]. There is no default
+    /// token.
+    ///
+    /// ```toml
+    /// default_profile = "production"
+    ///
+    /// [profiles.production]
+    /// token = "eyJhbG..."
+    ///
+    /// [profiles.staging]
+    /// url = "https://api.portal.biznetgio.dev/v1/neolites"
+    /// token = "eyJhbG..."
+    /// ```
+    pub fn load() -> Result<Self, crate::Error> {
+        Self::load_profile(env_var(ENV_PROFILE).as_deref())
+    }
+    /// Like [`Config::load`], with an explicit profile taking precedence over `NEOLITE_PROFILE`.
+    pub fn load_profile(profile: Option<&str>) -> Result<Self, crate::Error> {
+        let explicit_path = env_var(ENV_CONFIG_FILE).map(PathBuf::from);
+        let path = explicit_path.clone().or_else(default_config_path);
+        let file = match path {
+            Some(path) if path.exists() => Some(ConfigFile::read(&path)?),
+            _ => None,
+        };
+        if let (Some(path), None) = (&explicit_path, &file) {
+            return Err(crate::Error::Config(format!(
+                "Config file `{}` does not exist",
+                path.display()
+            )));
+        }
+        if let Some(name) = profile {
+            return match &file {
+                Some(file) => file.config(name),
+                None => Err(crate::Error::Config(format!(
+                    "Profile `{}` requested, but there is no config file",
+                    name
+                ))),
+            };
         }
+
+        // The URL and the token always come from the same source.
+        if env_var(ENV_TOKEN).is_some() {
+            return Self::from_env();
+        }
+        if env_var(ENV_URL).is_some() {
+            return Err(crate::Error::Config(format!(
+                "`{}` is set without `{}`. Set both, or neither to use the config file",
+                ENV_URL, ENV_TOKEN
+            )));
+        }
+        match &file {
+            Some(file) => match file.default_profile.as_deref() {
+                Some(name) => file.config(name),
+                // A missing `default` profile is fine, a missing named one is a typo.
+                None if file.profiles.contains_key(DEFAULT_PROFILE) => file.config(DEFAULT_PROFILE),
+                None => Err(no_token()),
+            },
+            None => Err(no_token()),
+        }
+    }
+}
+
+fn no_token() -> crate::Error {
+    crate::Error::Config(format!(
+        "No token found. Set `{}` or add one to the config file",
+        ENV_TOKEN
+    ))
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ConfigFile {
+    default_profile: Option<String>,
+    #[serde(default)]
+    profiles: BTreeMap<String, Profile>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Profile {
+    url: Option<String>,
+    token: Option<Secret>,
+}
+
+impl ConfigFile {
+    fn read(path: &Path) -> Result<Self, crate::Error> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            crate::Error::Config(format!("Failed to read `{}`: {}", path.display(), e))
+        })?;
+        toml::from_str(&text).map_err(|e| {
+            crate::Error::Config(format!("Failed to parse `{}`: {}", path.display(), e))
+        })
+    }
+    fn profile(&self, name: &str) -> Result<&Profile, crate::Error> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| crate::Error::Config(format!("Profile `{}` not found", name)))
+    }
+    /// The URL and token of the profile `name`, without any other source.
+    fn config(&self, name: &str) -> Result<Config, crate::Error> {
+        let profile = self.profile(name)?;
+        let token = profile
+            .token
+            .clone()
+            .ok_or_else(|| crate::Error::Config(format!("Profile `{}` has no `token`", name)))?;
+        let url = profile.url.as_deref().unwrap_or(DEFAULT_BASE_URL);
+        Ok(Config {
+            base_url: parse_url(url)?,
+            token,
+        })
+    }
+}
+
+fn default_config_path() -> Option<PathBuf> {
+    let base = env_var("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env_var("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(base.join("neolite").join("config.toml"))
+}
+
+fn env_var(name: &str) -> Option<String> {
+    env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+fn parse_url(url: &str) -> Result<http::Uri, crate::Error> {
+    url.parse()
+        .map_err(|e| crate::Error::Config(format!("Invalid URL `{}`: {}", url, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FILE: &str = r#"
+        default_profile = "staging"
+
+        [profiles.default]
+        token = "default-token"
+
+        [profiles.staging]
+        url = "https://staging.example.com/v1/neolites"
+        token = "staging-token"
+
+        [profiles.no-token]
+        url = "https://staging.example.com/v1/neolites"
+    "#;
+
+    // The only test touching the environment, so it can't race with another.
+    #[test]
+    fn never_mixes_url_and_token_from_different_sources() {
+        let path = env::temp_dir().join(format!("neolite-config-{}.toml", std::process::id()));
+        fs::write(&path, FILE).unwrap();
+        env::set_var(ENV_CONFIG_FILE, &path);
+        env::set_var(ENV_URL, "https://env.example.com/v1/neolites");
+        env::set_var(ENV_TOKEN, "env-token");
+
+        // A named profile ignores the environment.
+        let config = Config::load_profile(Some("default")).unwrap();
+        assert_eq!(config.base_url, DEFAULT_BASE_URL);
+        assert_eq!(config.token.expose(), "default-token");
+        assert!(Config::load_profile(Some("no-token")).is_err());
+
+        let config = Config::load_profile(None).unwrap();
+        assert_eq!(config.base_url, "https://env.example.com/v1/neolites");
+        assert_eq!(config.token.expose(), "env-token");
+
+        // The environment token is not sent to the `default_profile` URL.
+        env::remove_var(ENV_URL);
+        let config = Config::load_profile(None).unwrap();
+        assert_eq!(config.base_url, DEFAULT_BASE_URL);
+        assert_eq!(config.token.expose(), "env-token");
+
+        // Nor is the file token sent to the environment URL.
+        env::remove_var(ENV_TOKEN);
+        env::set_var(ENV_URL, "https://env.example.com/v1/neolites");
+        let error = Config::load_profile(None).unwrap_err();
+        assert!(matches!(error, crate::Error::Config(_)));
+
+        env::remove_var(ENV_URL);
+        let config = Config::load_profile(None).unwrap();
+        assert_eq!(config.base_url, "https://staging.example.com/v1/neolites");
+        assert_eq!(config.token.expose(), "staging-token");
+
+        env::remove_var(ENV_CONFIG_FILE);
+        fs::remove_file(&path).unwrap();
     }
 }
@@ -1,12 +1,18 @@
-use std::sync::Arc;
+use std::{net::IpAddr, sync::Arc};
 
+use chrono::{NaiveDate, NaiveDateTime};
+use futures::Stream;
 use serde::{Deserialize, Serialize};
 use serde_aux::field_attributes::deserialize_number_from_string;
 use serde_json as json;
 
-use crate::client::Client;
+use super::{
+    date::{option_date, option_datetime},
+    page::{self, Page},
+};
+use crate::{client::Client, money::Money, plan::BillingCycle, secret::Secret};
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum AccountStatus {
     Active,
     Pending,
@@ -38,6 +44,17 @@ impl Account {
         let response: Vec<AccountResource> = json::from_value(response)?;
         Ok(response)
     }
+    /// Streams accounts, fetching further pages as the stream is consumed.
+    pub fn stream(
+        &self,
+    ) -> impl Stream<Item = Result<AccountResource, crate::Error>> + Send + 'static {
+        let client = Arc::clone(&self.client);
+        // NOTE: The NEOLite REST API doesn't paginate yet, the cursor is unused.
+        page::paginate(move |_cursor| {
+            let account = Account::new(Arc::clone(&client));
+            async move { Ok(Page::last(account.list().await?)) }
+        })
+    }
     pub async fn list_with_status(
         &self,
         status: AccountStatus,
@@ -49,21 +66,17 @@ impl Account {
         let response: Vec<AccountResource> = json::from_value(response)?;
         Ok(response)
     }
-    // pub async fn get(&self, id: u32) -> Result<AccountResource, crate::Error> {
-    //     let response = self.client.get(&format!("/accounts/{id}")).await;
-    //     match response {
-    //         Ok(response) => {
-    //             let response: AccountResource = json::from_value(response)?;
-    //             Ok(response)
-    //         }
-    //         Err(e) => match e {
-    //             crate::Error::NotFound(_) => {
-    //                 Err(crate::Error::NotFound("Account not found".into()))
-    //             }
-    //             _ => Err(e),
-    //         },
-    //     }
-    // }
+    #[tracing::instrument(skip(self))]
+    pub async fn get(&self, id: u32) -> Result<AccountResource, crate::Error> {
+        let response = self.client.get(&format!("/accounts/{id}")).await;
+        match response {
+            Ok(response) => {
+                let response: AccountResource = json::from_value(response)?;
+                Ok(response)
+            }
+            Err(e) => Err(e.describe_not_found("Account not found")),
+        }
+    }
 
     //
     // Snapshots
@@ -74,6 +87,16 @@ impl Account {
         Ok(response)
     }
 
+    pub fn stream_snapshot(
+        &self,
+    ) -> impl Stream<Item = Result<SnapshotAccountResource, crate::Error>> + Send + 'static {
+        let client = Arc::clone(&self.client);
+        page::paginate(move |_cursor| {
+            let account = Account::new(Arc::clone(&client));
+            async move { Ok(Page::last(account.list_snapshot().await?)) }
+        })
+    }
+
     pub async fn get_snapshot(&self, id: u32) -> Result<SnapshotAccountResource, crate::Error> {
         let response = self.client.get(&format!("/snapshots/accounts/{id}")).await;
         match response {
@@ -81,27 +104,24 @@ impl Account {
                 let response: SnapshotAccountResource = json::from_value(response)?;
                 Ok(response)
             }
-            Err(e) => match e {
-                crate::Error::NotFound(_) => {
-                    Err(crate::Error::NotFound("Snapshot not found".into()))
-                }
-                _ => Err(e),
-            },
+            Err(e) => Err(e.describe_not_found("Snapshot not found")),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountResource {
     #[serde(deserialize_with = "deserialize_number_from_string")]
     #[serde(rename = "account_id")]
     pub id: u32,
     pub domain: String,
     pub status: AccountStatus,
-    pub billingcycle: String,
-    pub date_created: String,
-    pub next_due: String,
-    pub recurring_amount: i32,
+    pub billingcycle: BillingCycle,
+    #[serde(with = "option_date", default)]
+    pub date_created: Option<NaiveDate>,
+    #[serde(with = "option_date", default)]
+    pub next_due: Option<NaiveDate>,
+    pub recurring_amount: Money,
     pub extra_details: ExtraDetails,
     pub product_id: u32,
     pub product_name: String,
@@ -111,19 +131,23 @@ pub struct AccountResource {
     pub last_invoice: LastInvoice,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LastInvoice {
     pub id: u32,
     pub paid_id: u32,
     pub status: String,
-    pub date: String,
-    pub duedate: String,
-    pub paybefore: String,
-    pub datepaid: String,
+    #[serde(with = "option_date", default)]
+    pub date: Option<NaiveDate>,
+    #[serde(with = "option_date", default)]
+    pub duedate: Option<NaiveDate>,
+    #[serde(with = "option_date", default)]
+    pub paybefore: Option<NaiveDate>,
+    #[serde(with = "option_datetime", default)]
+    pub datepaid: Option<NaiveDateTime>,
     pub invoice_type: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtraDetails {
     pub region: String,
     pub region_label: String,
@@ -131,13 +155,43 @@ pub struct ExtraDetails {
     pub name: String,
     pub tenant_id: Option<String>,
     pub ciuser: String,
-    pub cipassword: String,
+    pub cipassword: Secret,
     #[serde(rename = "neosshkey_id")]
     pub keypair_id: u32,
     // pub keypair_name: String,
     pub sshkeys: String,
     pub osname: String,
     pub disk_size: String,
+    /// Details the SDK doesn't model. The portal doesn't document them.
+    #[serde(flatten)]
+    pub other: json::Map<String, json::Value>,
+}
+
+impl ExtraDetails {
+    /// IP addresses found among the undocumented details.
+    ///
+    /// The portal doesn't document which detail holds the VM's addresses, so
+    /// every string or list of strings in [`ExtraDetails::other`] is searched
+    /// for addresses separated by commas or whitespace.
+    pub fn ip_addresses(&self) -> Vec<IpAddr> {
+        let mut addresses = Vec::new();
+        for value in self.other.values() {
+            let values = match value {
+                json::Value::Array(values) => values.as_slice(),
+                value => std::slice::from_ref(value),
+            };
+            let words = values
+                .iter()
+                .filter_map(json::Value::as_str)
+                .flat_map(|text| text.split(|c: char| c == ',' || c.is_whitespace()));
+            for address in words.filter_map(|word| word.parse().ok()) {
+                if !addresses.contains(&address) {
+                    addresses.push(address);
+                }
+            }
+        }
+        addresses
+    }
 }
 
 //
@@ -0,0 +1,150 @@
+//! Lenient parsing of the dates the portal returns.
+//!
+//! Dates come as `2024-02-01` or `2024-02-01 10:00:00`. Unset ones come as an
+//! empty string, `null`, or a zero date like `0000-00-00`. Those become `None`.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Deserializer, Serializer};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub(crate) mod option_date {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        date: &Option<NaiveDate>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => serializer.collect_str(&date.format(DATE_FORMAT)),
+            None => serializer.serialize_str(""),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDate>, D::Error> {
+        let Some(value) = unset_as_none(deserializer)? else {
+            return Ok(None);
+        };
+        // A date with a time part is still a date.
+        let date = value.get(..10).unwrap_or(&value);
+        NaiveDate::parse_from_str(date, DATE_FORMAT)
+            .map(Some)
+            .map_err(|e| serde::de::Error::custom(format!("invalid date `{}`: {}", value, e)))
+    }
+}
+
+pub(crate) mod option_datetime {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        datetime: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match datetime {
+            Some(datetime) => serializer.collect_str(&datetime.format(DATETIME_FORMAT)),
+            None => serializer.serialize_str(""),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDateTime>, D::Error> {
+        let Some(value) = unset_as_none(deserializer)? else {
+            return Ok(None);
+        };
+        NaiveDateTime::parse_from_str(&value, DATETIME_FORMAT)
+            .or_else(|_| {
+                // A date alone is midnight of that day.
+                NaiveDate::parse_from_str(&value, DATE_FORMAT)
+                    .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+            })
+            .map(Some)
+            .map_err(|e| serde::de::Error::custom(format!("invalid date `{}`: {}", value, e)))
+    }
+}
+
+fn unset_as_none<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty() && !value.starts_with("0000-00-00")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use serde_json as json;
+
+    use super::*;
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct Dates {
+        #[serde(with = "option_date", default)]
+        date: Option<NaiveDate>,
+        #[serde(with = "option_datetime", default)]
+        datetime: Option<NaiveDateTime>,
+    }
+
+    fn parse(value: json::Value) -> Dates {
+        json::from_value(json::json!({ "date": value, "datetime": value })).unwrap()
+    }
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn reads_unset_dates_as_none() {
+        for value in [
+            json::json!(""),
+            json::json!("  "),
+            json::json!(null),
+            json::json!("0000-00-00"),
+            json::json!("0000-00-00 00:00:00"),
+        ] {
+            let dates = parse(value.clone());
+            assert_eq!(dates.date, None, "{}", value);
+            assert_eq!(dates.datetime, None, "{}", value);
+        }
+        let dates: Dates = json::from_str("{}").unwrap();
+        assert_eq!(dates.date, None);
+    }
+
+    #[test]
+    fn reads_dates_and_datetimes() {
+        let dates = parse(json::json!("2024-02-01"));
+        assert_eq!(dates.date, Some(date(2024, 2, 1)));
+        assert_eq!(dates.datetime, date(2024, 2, 1).and_hms_opt(0, 0, 0));
+
+        let dates = parse(json::json!("2024-02-01 10:30:15"));
+        assert_eq!(dates.date, Some(date(2024, 2, 1)));
+        assert_eq!(dates.datetime, date(2024, 2, 1).and_hms_opt(10, 30, 15));
+    }
+
+    #[test]
+    fn rejects_other_values() {
+        for value in [
+            json::json!("01/02/2024"),
+            json::json!("2024-02-30"),
+            json::json!(20240201),
+        ] {
+            let result = json::from_value::<Dates>(json::json!({ "date": value }));
+            assert!(result.is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn writes_unset_dates_as_empty_strings() {
+        let dates = parse(json::json!("2024-02-01 10:30:15"));
+        let value = json::to_value(&dates).unwrap();
+        assert_eq!(
+            value,
+            json::json!({ "date": "2024-02-01", "datetime": "2024-02-01 10:30:15" })
+        );
+        let value = json::to_value(parse(json::json!(null))).unwrap();
+        assert_eq!(value, json::json!({ "date": "", "datetime": "" }));
+    }
+}
@@ -1,8 +1,18 @@
-use std::sync::Arc;
+use std::{fmt, sync::Arc};
 
 use serde::{Deserialize, Serialize};
+use serde_aux::field_attributes::deserialize_number_from_string;
 
-use crate::{client::Client, keypair::Keypair, plan::Plan, snapshot::Snapshot, vm::VirtualMachine};
+use crate::{
+    account::{Account, AccountStatus},
+    client::Client,
+    keypair::Keypair,
+    plan::Plan,
+    report::Report,
+    snapshot::{Snapshot, SnapshotResource},
+    vm::{VirtualMachine, VirtualMachineResource},
+    wait::{self, WaitOptions},
+};
 
 pub struct Lite {
     client: Arc<Client>,
@@ -14,12 +24,18 @@ impl Lite {
             client: Arc::new(client),
         }
     }
+    pub async fn account(&self) -> Result<Account, crate::Error> {
+        Ok(Account::new(Arc::clone(&self.client)))
+    }
     pub async fn keypair(&self) -> Result<Keypair, crate::Error> {
         Ok(Keypair::new(Arc::clone(&self.client)))
     }
     pub async fn plan(&self) -> Result<Plan, crate::Error> {
         Ok(Plan::new(Arc::clone(&self.client)))
     }
+    pub async fn report(&self) -> Result<Report, crate::Error> {
+        Ok(Report::new(Arc::clone(&self.client)))
+    }
     pub async fn vm(&self) -> Result<VirtualMachine, crate::Error> {
         Ok(VirtualMachine::new(Arc::clone(&self.client)))
     }
@@ -28,8 +44,87 @@ impl Lite {
     }
 }
 
+/// An order placed on the portal, and the account it provisions.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct BillingResource {
-    pub order_id: String,
-    pub account_id: String,
+    pub order_id: OrderId,
+    pub account_id: AccountId,
+
+    #[serde(skip)]
+    client: Arc<Client>,
+}
+
+impl BillingResource {
+    pub(crate) fn with_client(mut self, client: Arc<Client>) -> Self {
+        self.client = client;
+        self
+    }
+    /// Waits until the ordered VM is provisioned and its details are available.
+    #[tracing::instrument(skip_all, fields(account_id = %self.account_id))]
+    pub async fn wait_for_vm(
+        &self,
+        opts: &WaitOptions,
+    ) -> Result<VirtualMachineResource, crate::Error> {
+        VirtualMachine::new(Arc::clone(&self.client))
+            .wait_for(
+                self.account_id.0,
+                |vm| vm.account_status == AccountStatus::Active,
+                opts,
+            )
+            .await
+    }
+    /// Waits until the ordered snapshot is provisioned.
+    #[tracing::instrument(skip_all, fields(account_id = %self.account_id))]
+    pub async fn wait_for_snapshot(
+        &self,
+        opts: &WaitOptions,
+    ) -> Result<SnapshotResource, crate::Error> {
+        let id = self.account_id.0;
+        let account = Account::new(Arc::clone(&self.client));
+        let account = &account;
+        let fetch = move || async move {
+            match account.get_snapshot(id).await {
+                Ok(snapshot) => Ok(Some(snapshot)),
+                Err(crate::Error::NotFound { .. }) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+        let snapshot = wait::poll(
+            format!("snapshot {}", id),
+            opts,
+            fetch,
+            |snapshot| snapshot.status == AccountStatus::Active,
+            |snapshot| format!("account {}", snapshot.status.as_str()),
+        )
+        .await?;
+        Ok(SnapshotResource {
+            id: snapshot.id,
+            name: snapshot.extra_details.name,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct OrderId(#[serde(deserialize_with = "deserialize_number_from_string")] pub u32);
+
+/// The id of an account, used by the API for VMs and snapshots alike.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct AccountId(#[serde(deserialize_with = "deserialize_number_from_string")] pub u32);
+
+impl fmt::Display for OrderId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl fmt::Display for AccountId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl From<AccountId> for u32 {
+    fn from(id: AccountId) -> Self {
+        id.0
+    }
 }
@@ -1,7 +1,14 @@
-mod account;
+mod date;
+mod page;
 
+pub mod account;
+#[cfg(feature = "keygen")]
+pub mod keygen;
 pub mod keypair;
 pub mod lite;
+pub mod preflight;
 pub mod products;
+pub mod report;
+pub mod request;
 pub mod snapshot;
 pub mod vm;
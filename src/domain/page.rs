@@ -0,0 +1,41 @@
+use std::future::Future;
+
+use futures::{stream, Stream, TryStreamExt};
+
+/// One page of a listing.
+///
+/// The NEO Lite API returns whole listings today, so every page is the last
+/// one. Once it paginates, `next` carries the cursor of the following page.
+pub(crate) struct Page<T> {
+    pub items: Vec<T>,
+    pub next: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn last(items: Vec<T>) -> Self {
+        Self { items, next: None }
+    }
+}
+
+/// Streams the items of every page, fetching the next page only once the
+/// previous one has been consumed.
+pub(crate) fn paginate<T, F, Fut>(fetch: F) -> impl Stream<Item = Result<T, crate::Error>>
+where
+    F: FnMut(Option<String>) -> Fut,
+    Fut: Future<Output = Result<Page<T>, crate::Error>>,
+{
+    // `None` state means the last page has been fetched.
+    let pages = stream::try_unfold(
+        (Some(None), fetch),
+        |(cursor, mut fetch): (Option<Option<String>>, F)| async move {
+            let Some(cursor) = cursor else {
+                return Ok::<_, crate::Error>(None);
+            };
+            let page = fetch(cursor).await?;
+            Ok(Some((page.items, (page.next.map(Some), fetch))))
+        },
+    );
+    pages
+        .map_ok(|items| stream::iter(items.into_iter().map(Ok)))
+        .try_flatten()
+}
@@ -0,0 +1,302 @@
+use std::{fmt, sync::Arc};
+
+use crate::{
+    client::Client,
+    ip::Ip,
+    keypair::Keypair,
+    os::{Os, OsResource},
+    plan::{Plan, PlanResource},
+    request::{CreateVmRequest, OsSelection},
+};
+
+/// What a [`PreflightCheck`] verified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CheckKind {
+    /// The request passes [`CreateVmRequest::validate`].
+    Request,
+    Plan,
+    BillingCycle,
+    IpAvailability,
+    Os,
+    /// The OS template supports the cores and memory of the plan.
+    OsFitsPlan,
+    Keypair,
+}
+
+impl CheckKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Request => "request",
+            Self::Plan => "plan",
+            Self::BillingCycle => "billing cycle",
+            Self::IpAvailability => "IP availability",
+            Self::Os => "OS",
+            Self::OsFitsPlan => "OS fits plan",
+            Self::Keypair => "keypair",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreflightCheck {
+    pub kind: CheckKind,
+    /// Why the check failed, `None` if it passed.
+    pub failure: Option<String>,
+}
+
+impl PreflightCheck {
+    pub fn passed(&self) -> bool {
+        self.failure.is_none()
+    }
+}
+
+/// Outcome of [`VirtualMachine::preflight`](crate::vm::VirtualMachine::preflight).
+///
+/// Checks that depend on a failed one, like the billing cycle on a missing
+/// plan, are not run and not listed.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PreflightReport {
+    pub checks: Vec<PreflightCheck>,
+}
+
+impl PreflightReport {
+    /// Whether every check passed, so the order is expected to go through.
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(PreflightCheck::passed)
+    }
+    pub fn failures(&self) -> impl Iterator<Item = &PreflightCheck> {
+        self.checks.iter().filter(|check| !check.passed())
+    }
+    fn pass(&mut self, kind: CheckKind) {
+        self.checks.push(PreflightCheck {
+            kind,
+            failure: None,
+        });
+    }
+    fn fail(&mut self, kind: CheckKind, failure: impl Into<String>) {
+        self.checks.push(PreflightCheck {
+            kind,
+            failure: Some(failure.into()),
+        });
+    }
+}
+
+impl fmt::Display for PreflightReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for check in &self.checks {
+            match &check.failure {
+                None => writeln!(f, "ok   {}", check.kind.as_str())?,
+                Some(failure) => writeln!(f, "fail {}: {}", check.kind.as_str(), failure)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs every check of a VM order. Only unexpected API errors are returned
+/// as `Err`, a missing plan, OS or keypair is a failed check.
+pub(crate) async fn run(
+    client: &Arc<Client>,
+    request: &CreateVmRequest,
+) -> Result<PreflightReport, crate::Error> {
+    let mut report = PreflightReport::default();
+    match request.validate() {
+        Ok(()) => report.pass(CheckKind::Request),
+        Err(crate::Error::Validation(problems)) => {
+            report.fail(CheckKind::Request, problems.join("; "))
+        }
+        Err(e) => return Err(e),
+    }
+
+    let keypair = Keypair::new(Arc::clone(client));
+    match request.keypair_id {
+        None => report.fail(CheckKind::Keypair, "No keypair selected"),
+        Some(id) => match found(keypair.get(id).await)? {
+            Some(_) => report.pass(CheckKind::Keypair),
+            None => report.fail(CheckKind::Keypair, format!("Keypair {} not found", id)),
+        },
+    }
+
+    let Some(plan_id) = request.plan_id else {
+        report.fail(CheckKind::Plan, "No plan selected");
+        return Ok(report);
+    };
+    let plan = Plan::new(Arc::clone(client));
+    let Some(plan) = found(plan.get_vm(plan_id).await)? else {
+        report.fail(CheckKind::Plan, format!("Plan {} not found", plan_id));
+        return Ok(report);
+    };
+    report.pass(CheckKind::Plan);
+
+    if plan
+        .billing
+        .iter()
+        .any(|billing| billing.cycle == request.cycle)
+    {
+        report.pass(CheckKind::BillingCycle);
+    } else {
+        let cycles: Vec<&str> = plan.billing.iter().map(|b| b.cycle.as_str()).collect();
+        report.fail(
+            CheckKind::BillingCycle,
+            format!(
+                "Plan {} is not billed `{}`. Available: {}",
+                plan.id,
+                request.cycle,
+                cycles.join(", ")
+            ),
+        );
+    }
+
+    if Ip::new(Arc::clone(client), plan.id).is_available().await? {
+        report.pass(CheckKind::IpAvailability);
+    } else {
+        report.fail(
+            CheckKind::IpAvailability,
+            format!("No IP address is available for plan {}", plan.id),
+        );
+    }
+
+    let oses = Os::new(Arc::clone(client), plan.id).list().await?;
+    let os = match &request.os {
+        None => None,
+        Some(OsSelection::Id(id)) => oses.iter().find(|os| os.id == *id),
+        Some(OsSelection::Name(name)) => oses.iter().find(|os| &os.name == name),
+    };
+    match (os, &request.os) {
+        (Some(os), _) => {
+            report.pass(CheckKind::Os);
+            check_os_fits(&mut report, os, &plan);
+        }
+        (None, None) => report.fail(CheckKind::Os, "No OS selected"),
+        (None, Some(selection)) => {
+            let selection = match selection {
+                OsSelection::Id(id) => id.to_string(),
+                OsSelection::Name(name) => format!("`{}`", name),
+            };
+            report.fail(
+                CheckKind::Os,
+                format!("OS {} is not offered for plan {}", selection, plan.id),
+            );
+        }
+    }
+
+    Ok(report)
+}
+
+fn check_os_fits(report: &mut PreflightReport, os: &OsResource, plan: &PlanResource) {
+    const MIB: u64 = 1024 * 1024;
+    let mut problems = Vec::new();
+    if plan.options.cores > os.maxcpu {
+        problems.push(format!(
+            "{} cores exceed the {} supported by `{}`",
+            plan.options.cores, os.maxcpu, os.name
+        ));
+    }
+    // The plan memory is in MB, the OS limit in bytes.
+    if u64::from(plan.options.memory) * MIB > os.maxmem {
+        problems.push(format!(
+            "{} MB of memory exceed the {} MB supported by `{}`",
+            plan.options.memory,
+            os.maxmem / MIB,
+            os.name
+        ));
+    }
+    if problems.is_empty() {
+        report.pass(CheckKind::OsFitsPlan);
+    } else {
+        report.fail(CheckKind::OsFitsPlan, problems.join("; "));
+    }
+}
+
+fn found<T>(result: Result<T, crate::Error>) -> Result<Option<T>, crate::Error> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(crate::Error::NotFound { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(all(test, feature = "mock"))]
+mod tests {
+    use super::*;
+    use crate::{mock::MockServer, plan::BillingCycle};
+
+    async fn preflight(mock: &Arc<MockServer>, request: CreateVmRequest) -> PreflightReport {
+        run(&Arc::new(mock.client()), &request).await.unwrap()
+    }
+
+    async fn request(mock: &Arc<MockServer>) -> CreateVmRequest {
+        let keypair = Keypair::new(Arc::new(mock.client()));
+        let keypair = keypair.create("gandalf0").await.unwrap();
+        CreateVmRequest::new("thorin-os2", "thethorin", "SpeakFriendAndEnter123")
+            .plan_id(1538)
+            .os_id(1001)
+            .keypair_id(keypair.id)
+    }
+
+    fn failed(report: &PreflightReport) -> Vec<CheckKind> {
+        report.failures().map(|check| check.kind).collect()
+    }
+
+    #[tokio::test]
+    async fn passes_a_valid_order() {
+        let mock = Arc::new(MockServer::new());
+        let report = preflight(&mock, request(&mock).await).await;
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!(report.checks.len(), 7);
+    }
+
+    #[tokio::test]
+    async fn reports_a_missing_keypair() {
+        let mock = Arc::new(MockServer::new());
+        let report = preflight(&mock, request(&mock).await.keypair_id(99)).await;
+        assert_eq!(failed(&report), [CheckKind::Keypair]);
+        assert_eq!(
+            report.failures().next().unwrap().failure.as_deref(),
+            Some("Keypair 99 not found")
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_an_unsupported_billing_cycle() {
+        let mock = Arc::new(MockServer::new());
+        let request = request(&mock)
+            .await
+            .plan_id(1539)
+            .cycle(BillingCycle::Annually);
+        let report = preflight(&mock, request).await;
+        assert_eq!(failed(&report), [CheckKind::BillingCycle]);
+    }
+
+    #[tokio::test]
+    async fn reports_no_available_ip() {
+        let mock = Arc::new(MockServer::new());
+        mock.set_ip_available(1538, false);
+        let report = preflight(&mock, request(&mock).await).await;
+        assert_eq!(failed(&report), [CheckKind::IpAvailability]);
+    }
+
+    #[tokio::test]
+    async fn reports_an_os_too_small_for_the_plan() {
+        let mock = Arc::new(MockServer::new());
+        // 4 cores and 8 GB, while rockylinux-9 supports 2 cores and 2 GB.
+        let request = request(&mock).await.plan_id(1540).os_id(1003);
+        let report = preflight(&mock, request).await;
+        assert_eq!(failed(&report), [CheckKind::OsFitsPlan]);
+        let failure = report.failures().next().unwrap().failure.clone().unwrap();
+        assert!(failure.contains("4 cores"), "{}", failure);
+        assert!(failure.contains("8192 MB"), "{}", failure);
+    }
+
+    #[tokio::test]
+    async fn stops_when_the_plan_is_missing() {
+        let mock = Arc::new(MockServer::new());
+        let report = preflight(&mock, request(&mock).await.plan_id(9999)).await;
+        assert_eq!(failed(&report), [CheckKind::Plan]);
+        let kinds: Vec<CheckKind> = report.checks.iter().map(|check| check.kind).collect();
+        assert_eq!(
+            kinds,
+            [CheckKind::Request, CheckKind::Keypair, CheckKind::Plan]
+        );
+    }
+}
@@ -1,3 +1,4 @@
 pub mod ip;
 pub mod os;
 pub mod plan;
+pub mod quote;
@@ -31,7 +31,7 @@ impl Os {
                 return Ok(os);
             }
         }
-        Err(crate::Error::NotFound("OS is not found".to_string()))
+        Err(crate::Error::not_found("OS is not found"))
     }
 }
 
@@ -1,10 +1,11 @@
-use std::sync::Arc;
+use std::{convert::Infallible, fmt, str::FromStr, sync::Arc};
 
-use serde::{Deserialize, Serialize};
+use futures::{stream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json as json;
 
 use super::{ip::Ip, os::Os};
-use crate::client::Client;
+use crate::{client::Client, money::Money, vm::DEFAULT_CONCURRENCY};
 
 #[derive(Debug)]
 pub struct Plan {
@@ -18,8 +19,44 @@ impl Plan {
     pub async fn list_vm(&self) -> Result<Vec<PlanResource>, crate::Error> {
         let response = self.client.get("/products").await?;
         let response: Vec<PlanResource> = json::from_value(response)?;
+        let response = response
+            .into_iter()
+            .map(|plan| plan.with_client(Arc::clone(&self.client)))
+            .collect();
         Ok(response)
     }
+    /// Lists the VM plans that meet `requirements`, cheapest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn find_vm(
+        &self,
+        requirements: &VmRequirements,
+    ) -> Result<Vec<PlanResource>, crate::Error> {
+        let mut candidates: Vec<(Money, PlanResource)> = self
+            .list_vm()
+            .await?
+            .into_iter()
+            .filter_map(|plan| {
+                let price = requirements.price_of(&plan)?;
+                Some((price, plan))
+            })
+            .collect();
+
+        if requirements.ip_available {
+            let available: Vec<bool> = stream::iter(&candidates)
+                .map(|(_, plan)| {
+                    let ip = Ip::new(Arc::clone(&self.client), plan.id);
+                    async move { ip.is_available().await }
+                })
+                .buffered(DEFAULT_CONCURRENCY)
+                .try_collect()
+                .await?;
+            let mut available = available.into_iter();
+            candidates.retain(|_| available.next().unwrap_or(false));
+        }
+
+        candidates.sort_by_key(|(price, plan)| (*price, plan.id));
+        Ok(candidates.into_iter().map(|(_, plan)| plan).collect())
+    }
     pub async fn get_vm(&self, id: u32) -> Result<PlanResource, crate::Error> {
         let response = self.client.get(&format!("/products/{id}")).await?;
         let response: PlanResource = json::from_value(response)?;
@@ -44,6 +81,76 @@ impl Plan {
     }
 }
 
+/// What [`Plan::find_vm`] looks for.
+#[derive(Debug, Clone)]
+pub struct VmRequirements {
+    cycle: BillingCycle,
+    min_cores: u32,
+    min_memory: u32,
+    category: Option<String>,
+    max_price: Option<Money>,
+    ip_available: bool,
+}
+
+impl VmRequirements {
+    /// Plans billed every `cycle`, priced for that cycle.
+    pub fn new(cycle: BillingCycle) -> Self {
+        Self {
+            cycle,
+            min_cores: 0,
+            min_memory: 0,
+            category: None,
+            max_price: None,
+            ip_available: false,
+        }
+    }
+    pub fn min_cores(mut self, cores: u32) -> Self {
+        self.min_cores = cores;
+        self
+    }
+    /// Minimum memory in MB.
+    pub fn min_memory(mut self, memory: u32) -> Self {
+        self.min_memory = memory;
+        self
+    }
+    /// Category name, like `NEO Lite Pro`. Matched case-insensitively.
+    pub fn category(mut self, category: impl Into<String>) -> Self {
+        self.category = Some(category.into());
+        self
+    }
+    /// Maximum price per cycle, without components.
+    pub fn max_price(mut self, price: Money) -> Self {
+        self.max_price = Some(price);
+        self
+    }
+    /// Only plans with a public IP left to assign.
+    /// This costs a request per matching plan.
+    pub fn ip_available(mut self, ip_available: bool) -> Self {
+        self.ip_available = ip_available;
+        self
+    }
+    /// Price of `plan` for the cycle, `None` if it doesn't qualify.
+    fn price_of(&self, plan: &PlanResource) -> Option<Money> {
+        let options = &plan.options;
+        if options.cores < self.min_cores || options.memory < self.min_memory {
+            return None;
+        }
+        if let Some(category) = &self.category {
+            if !plan.category_name.eq_ignore_ascii_case(category) {
+                return None;
+            }
+        }
+        let billing = plan
+            .billing
+            .iter()
+            .find(|billing| billing.cycle == self.cycle)?;
+        match self.max_price {
+            Some(max_price) if billing.price > max_price => None,
+            _ => Some(billing.price),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PlanResource {
     #[serde(rename = "product_id")]
@@ -71,24 +178,109 @@ impl PlanResource {
         let ip = Ip::new(Arc::clone(&self.client), self.id);
         Ok(ip)
     }
-    pub async fn get_billing(&self, label: &str) -> Result<Billing, crate::Error> {
+    pub async fn get_billing(&self, cycle: BillingCycle) -> Result<Billing, crate::Error> {
         for billing in &self.billing {
-            if billing.label == label {
+            if billing.cycle == cycle {
                 return Ok(billing.clone());
             }
         }
-        Err(crate::Error::NotFound("Billing is not found".to_string()))
+        Err(crate::Error::not_found("Billing is not found"))
     }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Billing {
     pub label: String,
-    pub cycle: String,
-    pub price: u32,
+    pub cycle: BillingCycle,
+    pub price: Money,
     pub components: Option<Vec<Component>>,
 }
 
+/// How often an account is invoiced.
+///
+/// Cycles the SDK doesn't know yet are kept as [`BillingCycle::Other`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BillingCycle {
+    Monthly,
+    Quarterly,
+    SemiAnnually,
+    Annually,
+    Other(String),
+}
+
+impl BillingCycle {
+    /// The cycle string the API expects, like `semiannually`.
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Monthly => "monthly",
+            Self::Quarterly => "quarterly",
+            Self::SemiAnnually => "semiannually",
+            Self::Annually => "annually",
+            Self::Other(cycle) => cycle,
+        }
+    }
+    /// Length of the cycle, `None` for [`BillingCycle::Other`].
+    pub fn months(&self) -> Option<u32> {
+        match self {
+            Self::Monthly => Some(1),
+            Self::Quarterly => Some(3),
+            Self::SemiAnnually => Some(6),
+            Self::Annually => Some(12),
+            Self::Other(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for BillingCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Accepts both the API cycles (`semiannually`) and the portal labels
+/// (`Semi-Annually`).
+impl FromStr for BillingCycle {
+    type Err = Infallible;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized: String = value
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .map(|c| c.to_ascii_lowercase())
+            .collect();
+        let cycle = match normalized.as_str() {
+            "monthly" => Self::Monthly,
+            "quarterly" => Self::Quarterly,
+            "semiannually" => Self::SemiAnnually,
+            "annually" => Self::Annually,
+            _ => Self::Other(value.to_string()),
+        };
+        Ok(cycle)
+    }
+}
+
+impl From<&str> for BillingCycle {
+    fn from(value: &str) -> Self {
+        match value.parse() {
+            Ok(cycle) => cycle,
+            Err(never) => match never {},
+        }
+    }
+}
+
+impl Serialize for BillingCycle {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for BillingCycle {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(Self::from(value.as_str()))
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Component {
     pub label: String,
@@ -100,7 +292,7 @@ pub struct Component {
 pub struct Price {
     pub qty_min: i32,
     pub qty_max: i32,
-    pub price: u32,
+    pub price: Money,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
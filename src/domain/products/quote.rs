@@ -0,0 +1,238 @@
+use super::plan::{Billing, BillingCycle, Component, PlanResource};
+use crate::money::Money;
+
+/// The component field priced by the additional storage of a VM, in GB.
+pub const DISK_SIZE_FIELD: &str = "disk_size";
+
+/// Price of a plan for one billing cycle, including its components.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Quote {
+    pub plan_id: u32,
+    pub cycle: BillingCycle,
+    /// Price of the plan alone.
+    pub base: Money,
+    pub components: Vec<ComponentQuote>,
+    pub total: Money,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentQuote {
+    pub label: String,
+    pub field: String,
+    pub quantity: u32,
+    pub unit_price: Money,
+    pub price: Money,
+}
+
+/// Prices before and after a change to a VM, see
+/// [`VirtualMachineResource::quote_change_storage`](crate::vm::VirtualMachineResource::quote_change_storage).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeQuote {
+    pub current: Quote,
+    pub new: Quote,
+}
+
+impl ChangeQuote {
+    /// How much more the new setup costs per cycle. Negative when it is cheaper.
+    ///
+    /// When the cycles differ, compare `current.total` and `new.total` yourself.
+    pub fn difference(&self) -> Money {
+        self.new.total - self.current.total
+    }
+}
+
+impl PlanResource {
+    /// Disk size in GB included in the plan price, if the description
+    /// states it.
+    ///
+    /// The API has no field for it, so this is a best effort reading of the
+    /// free-text description, like the `20 GB SSD` of `1 vCPU, 1 GB RAM,
+    /// 20 GB SSD`. Prefer a size you know when there is one.
+    pub fn included_disk(&self) -> Option<u32> {
+        self.description.split(',').find_map(|part| {
+            let part = part.to_ascii_lowercase();
+            if part.contains("ram") || part.contains("memory") {
+                return None;
+            }
+            let (before, _) = part.split_once("gb")?;
+            let before = before.trim_end();
+            let start = before
+                .rfind(|c: char| !c.is_ascii_digit())
+                .map_or(0, |i| i + 1);
+            before[start..].parse().ok()
+        })
+    }
+    /// Prices the plan for `cycle` with `extra_disk` GB of additional storage
+    /// on top of the disk included in the plan.
+    pub fn quote(&self, cycle: BillingCycle, extra_disk: u32) -> Result<Quote, crate::Error> {
+        let billing = self
+            .billing
+            .iter()
+            .find(|billing| billing.cycle == cycle)
+            .ok_or_else(|| {
+                crate::Error::InvalidArgument(format!(
+                    "Plan `{}` has no `{}` billing cycle",
+                    self.id, cycle
+                ))
+            })?;
+        billing.quote(self.id, extra_disk)
+    }
+}
+
+impl Billing {
+    fn quote(&self, plan_id: u32, extra_disk: u32) -> Result<Quote, crate::Error> {
+        // Only the additional storage is sized by the SDK. Other components
+        // are not ordered.
+        let disk = self
+            .components
+            .iter()
+            .flatten()
+            .find(|component| component.field == DISK_SIZE_FIELD);
+        let components = match disk {
+            // Without additional storage the component is not ordered at all,
+            // even when its tiers start above zero.
+            _ if extra_disk == 0 => Vec::new(),
+            Some(disk) => vec![disk.quote(extra_disk)?],
+            None => {
+                return Err(crate::Error::InvalidArgument(format!(
+                    "Plan `{}` has no additional storage for the `{}` billing cycle",
+                    plan_id, self.cycle
+                )))
+            }
+        };
+        let total = self.price + components.iter().map(|c| c.price).sum();
+        Ok(Quote {
+            plan_id,
+            cycle: self.cycle.clone(),
+            base: self.price,
+            components,
+            total,
+        })
+    }
+}
+
+impl Component {
+    /// Price of a single unit when ordering `quantity` units.
+    ///
+    /// The tier that contains `quantity` applies to every unit.
+    pub fn unit_price(&self, quantity: u32) -> Result<Money, crate::Error> {
+        let quantity = i64::from(quantity);
+        self.prices
+            .iter()
+            .find(|tier| (i64::from(tier.qty_min)..=i64::from(tier.qty_max)).contains(&quantity))
+            .map(|tier| tier.price)
+            .ok_or_else(|| {
+                crate::Error::InvalidArgument(format!(
+                    "{} of {} is outside of the available tiers{}",
+                    self.label,
+                    quantity,
+                    self.range()
+                        .map(|(min, max)| format!(" ({} to {})", min, max))
+                        .unwrap_or_default()
+                ))
+            })
+    }
+    fn quote(&self, quantity: u32) -> Result<ComponentQuote, crate::Error> {
+        let unit_price = self.unit_price(quantity)?;
+        let price = unit_price
+            .checked_mul(i64::from(quantity))
+            .ok_or_else(|| crate::Error::InvalidArgument(format!("{} is too large", self.label)))?;
+        Ok(ComponentQuote {
+            label: self.label.clone(),
+            field: self.field.clone(),
+            quantity,
+            unit_price,
+            price,
+        })
+    }
+    fn range(&self) -> Option<(i32, i32)> {
+        let min = self.prices.iter().map(|tier| tier.qty_min).min()?;
+        let max = self.prices.iter().map(|tier| tier.qty_max).max()?;
+        Some((min, max))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json as json;
+
+    use super::*;
+
+    fn plan(tiers: json::Value) -> PlanResource {
+        json::from_value(json::json!({
+            "product_id": 1538,
+            "name": "NEO Lite Pro 1.1",
+            "description": "1 vCPU, 1 GB RAM, 20 GB SSD",
+            "category_id": 30,
+            "category_name": "NEO Lite Pro",
+            "options": { "type": "vm", "cores": 1, "memory": 1024, "allow_downgrade": 0 },
+            "billing": [{
+                "label": "Monthly",
+                "cycle": "monthly",
+                "price": 120000,
+                "components": [{
+                    "label": "Additional Storage",
+                    "field": "disk_size",
+                    "prices": tiers
+                }]
+            }]
+        }))
+        .unwrap()
+    }
+
+    fn tiers() -> json::Value {
+        json::json!([
+            { "qty_min": 0, "qty_max": 100, "price": 1500 },
+            { "qty_min": 101, "qty_max": 500, "price": 1200 }
+        ])
+    }
+
+    #[test]
+    fn reads_the_included_disk_from_the_description() {
+        let mut plan = plan(tiers());
+        assert_eq!(plan.included_disk(), Some(20));
+        for (description, disk) in [
+            ("1 vCPU, 1GB RAM, 20GB SSD", Some(20)),
+            ("SSD 40 GB, 2 GB RAM", Some(40)),
+            ("80 GB NVMe", Some(80)),
+            ("4 GB memory", None),
+            ("Snapshot of a NEO Lite virtual machine", None),
+        ] {
+            plan.description = description.to_string();
+            assert_eq!(plan.included_disk(), disk, "{}", description);
+        }
+    }
+
+    #[test]
+    fn prices_additional_storage_by_tier() {
+        let plan = plan(tiers());
+        let quote = plan.quote(BillingCycle::Monthly, 0).unwrap();
+        assert!(quote.components.is_empty());
+        assert_eq!(quote.total, Money::idr(120000));
+
+        let quote = plan.quote(BillingCycle::Monthly, 30).unwrap();
+        assert_eq!(quote.components[0].unit_price, Money::idr(1500));
+        assert_eq!(quote.total, Money::idr(120000 + 30 * 1500));
+
+        let quote = plan.quote(BillingCycle::Monthly, 150).unwrap();
+        assert_eq!(quote.total, Money::idr(120000 + 150 * 1200));
+    }
+
+    #[test]
+    fn prices_plans_without_storage_when_tiers_start_above_zero() {
+        let plan = plan(json::json!([{ "qty_min": 10, "qty_max": 100, "price": 1500 }]));
+        let quote = plan.quote(BillingCycle::Monthly, 0).unwrap();
+        assert!(quote.components.is_empty());
+        assert_eq!(quote.total, Money::idr(120000));
+    }
+
+    #[test]
+    fn rejects_quantities_outside_the_tiers() {
+        let tiered = plan(tiers());
+        assert!(tiered.quote(BillingCycle::Monthly, 501).is_err());
+        assert!(tiered.quote(BillingCycle::Annually, 0).is_err());
+
+        let plan = plan(json::json!([{ "qty_min": 10, "qty_max": 100, "price": 1500 }]));
+        assert!(plan.quote(BillingCycle::Monthly, 5).is_err());
+    }
+}
@@ -0,0 +1,229 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use chrono::NaiveDate;
+
+use super::account::{Account, AccountResource, AccountStatus};
+use crate::{client::Client, money::Money, plan::BillingCycle};
+
+pub struct Report {
+    client: Arc<Client>,
+}
+
+impl Report {
+    pub fn new(client: Arc<Client>) -> Self {
+        Self { client }
+    }
+    /// Monthly spend of every account that is not terminated.
+    #[tracing::instrument(skip(self))]
+    pub async fn cost(&self) -> Result<CostReport, crate::Error> {
+        let accounts = Account::new(Arc::clone(&self.client)).list().await?;
+        Ok(CostReport::from_accounts(&accounts))
+    }
+    /// Accounts due for renewal between `from` and `until`, both inclusive,
+    /// soonest first.
+    #[tracing::instrument(skip(self))]
+    pub async fn renewals(
+        &self,
+        from: NaiveDate,
+        until: NaiveDate,
+    ) -> Result<Vec<Renewal>, crate::Error> {
+        let accounts = Account::new(Arc::clone(&self.client)).list().await?;
+        Ok(renewals(&accounts, from, until))
+    }
+}
+
+/// Spend normalized to a monthly amount.
+///
+/// A quarterly account costing IDR 300.000 counts as IDR 100.000 a month.
+/// Accounts with a billing cycle that can't be normalized are left out of
+/// the totals and listed in `unknown_cycle`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CostReport {
+    pub total: Money,
+    pub by_region: BTreeMap<String, Money>,
+    pub by_product: BTreeMap<String, Money>,
+    pub by_status: BTreeMap<AccountStatus, Money>,
+    /// IDs of the accounts left out of the totals.
+    pub unknown_cycle: Vec<u32>,
+}
+
+impl CostReport {
+    pub fn from_accounts(accounts: &[AccountResource]) -> Self {
+        let mut report = Self::default();
+        for account in accounts {
+            if account.status == AccountStatus::Terminated {
+                continue;
+            }
+            let Some(amount) = monthly_amount(account) else {
+                report.unknown_cycle.push(account.id);
+                continue;
+            };
+            report.total += amount;
+            *report
+                .by_region
+                .entry(account.extra_details.region.clone())
+                .or_default() += amount;
+            *report
+                .by_product
+                .entry(account.product_name.clone())
+                .or_default() += amount;
+            *report.by_status.entry(account.status).or_default() += amount;
+        }
+        report
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Renewal {
+    pub account_id: u32,
+    pub name: String,
+    pub product_name: String,
+    pub region: String,
+    pub billingcycle: BillingCycle,
+    pub next_due: NaiveDate,
+    pub recurring_amount: Money,
+}
+
+/// Accounts due for renewal between `from` and `until`, both inclusive,
+/// soonest first. Terminated accounts and accounts without a due date are skipped.
+pub fn renewals(accounts: &[AccountResource], from: NaiveDate, until: NaiveDate) -> Vec<Renewal> {
+    let mut renewals: Vec<Renewal> = accounts
+        .iter()
+        .filter(|account| account.status != AccountStatus::Terminated)
+        .filter_map(|account| {
+            let next_due = account.next_due?;
+            (from..=until).contains(&next_due).then(|| Renewal {
+                account_id: account.id,
+                name: account.extra_details.name.clone(),
+                product_name: account.product_name.clone(),
+                region: account.extra_details.region.clone(),
+                billingcycle: account.billingcycle.clone(),
+                next_due,
+                recurring_amount: account.recurring_amount,
+            })
+        })
+        .collect();
+    renewals.sort_by_key(|renewal| (renewal.next_due, renewal.account_id));
+    renewals
+}
+
+fn monthly_amount(account: &AccountResource) -> Option<Money> {
+    let months = account.billingcycle.months()?;
+    Some(account.recurring_amount.div_round(i64::from(months)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json as json;
+
+    use super::*;
+
+    fn account(id: u32, status: &str, cycle: &str, amount: i64, next_due: &str) -> AccountResource {
+        json::from_value(json::json!({
+            "account_id": id.to_string(),
+            "domain": "",
+            "status": status,
+            "billingcycle": cycle,
+            "date_created": "2024-01-01",
+            "next_due": next_due,
+            "recurring_amount": amount,
+            "extra_details": {
+                "region": if id % 2 == 0 { "jkt" } else { "sby" },
+                "region_label": "",
+                "description": "",
+                "name": format!("vm-{}", id),
+                "tenant_id": null,
+                "ciuser": "thethorin",
+                "cipassword": "secret",
+                "neosshkey_id": 1,
+                "sshkeys": "",
+                "osname": "ubuntu-22.04",
+                "disk_size": "20",
+            },
+            "product_id": 1538,
+            "product_name": "NEO Lite Pro 1.1",
+            "description": "",
+            "category_id": 30,
+            "category_name": "NEO Lite Pro",
+            "last_invoice": {
+                "id": 1,
+                "paid_id": 0,
+                "status": "Paid",
+                "invoice_type": "new",
+            },
+        }))
+        .unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 2, day).unwrap()
+    }
+
+    #[test]
+    fn normalizes_every_cycle_to_a_month() {
+        let cases = [
+            ("Monthly", 120000, 120000),
+            ("Quarterly", 300000, 100000),
+            ("Semi-Annually", 600000, 100000),
+            ("Annually", 1296000, 108000),
+            ("Annually", 1000, 83),
+        ];
+        for (cycle, amount, monthly) in cases {
+            let account = account(1, "Active", cycle, amount, "2024-02-01");
+            assert_eq!(
+                monthly_amount(&account),
+                Some(Money::idr(monthly)),
+                "{}",
+                cycle
+            );
+        }
+        let account = account(1, "Active", "Biennially", 1000, "2024-02-01");
+        assert_eq!(monthly_amount(&account), None);
+    }
+
+    #[test]
+    fn sums_accounts_and_lists_unknown_cycles() {
+        let accounts = [
+            account(1, "Active", "Monthly", 120000, "2024-02-01"),
+            account(2, "Suspended", "Quarterly", 300000, "2024-02-01"),
+            account(3, "Active", "Biennially", 2400000, "2024-02-01"),
+            account(4, "Terminated", "Monthly", 50000, "2024-02-01"),
+            account(5, "Terminated", "Biennially", 50000, "2024-02-01"),
+        ];
+        let report = CostReport::from_accounts(&accounts);
+
+        assert_eq!(report.total, Money::idr(220000));
+        assert_eq!(report.unknown_cycle, [3]);
+        assert_eq!(report.by_region["sby"], Money::idr(120000));
+        assert_eq!(report.by_region["jkt"], Money::idr(100000));
+        assert_eq!(report.by_product["NEO Lite Pro 1.1"], Money::idr(220000));
+        assert_eq!(report.by_status[&AccountStatus::Active], Money::idr(120000));
+        assert_eq!(
+            report.by_status[&AccountStatus::Suspended],
+            Money::idr(100000)
+        );
+        assert!(!report.by_status.contains_key(&AccountStatus::Terminated));
+    }
+
+    #[test]
+    fn lists_renewals_within_the_inclusive_window() {
+        let accounts = [
+            account(1, "Active", "Monthly", 120000, "2024-02-20"),
+            account(2, "Active", "Monthly", 120000, "2024-02-10"),
+            account(3, "Active", "Monthly", 120000, "2024-02-09"),
+            account(4, "Active", "Monthly", 120000, "2024-02-21"),
+            account(5, "Suspended", "Monthly", 120000, "2024-02-15"),
+            account(6, "Terminated", "Monthly", 120000, "2024-02-15"),
+            account(7, "Active", "Monthly", 120000, "0000-00-00"),
+        ];
+        let renewals = renewals(&accounts, date(10), date(20));
+
+        let ids: Vec<u32> = renewals.iter().map(|renewal| renewal.account_id).collect();
+        assert_eq!(ids, [2, 5, 1]);
+        assert_eq!(renewals[0].next_due, date(10));
+        assert_eq!(renewals[0].name, "vm-2");
+        assert_eq!(renewals[0].region, "jkt");
+        assert_eq!(renewals[0].billingcycle, BillingCycle::Monthly);
+        assert_eq!(renewals[0].recurring_amount, Money::idr(120000));
+    }
+}
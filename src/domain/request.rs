@@ -0,0 +1,363 @@
+use crate::{
+    keypair::KeypairResource,
+    os::OsResource,
+    plan::{BillingCycle, PlanResource},
+    secret::Secret,
+    vm::VirtualMachineOptions,
+};
+
+// RFC 1123, section 2.1: a host name label has at most 63 characters.
+const NAME_MAX_LEN: usize = 63;
+// useradd(8), CAVEATS: "Usernames may only be up to 32 characters long."
+const USERNAME_MAX_LEN: usize = 32;
+// Accounts in the `/etc/passwd` shipped by both Debian (base-passwd) and
+// Fedora (setup). useradd(8) refuses to create a user that already exists.
+const RESERVED_USERNAMES: &[&str] = &[
+    "root", "daemon", "bin", "lp", "sync", "mail", "games", "nobody",
+];
+
+/// An order for a new VM, see [`VirtualMachine::create_with`](crate::vm::VirtualMachine::create_with).
+///
+/// The plan, OS and keypair can be given as ids or as fetched resources.
+/// [`CreateVmRequest::validate`] checks the request without any network call.
+///
+/// ```no_run
+/// # async fn run(vm: neolite::vm::VirtualMachine) -> Result<(), neolite::Error> {
+/// use neolite::{plan::BillingCycle, request::CreateVmRequest};
+///
+/// let request = CreateVmRequest::new("thorin-os2", "thethorin", "SpeakFriendAndEnter123")
+///     .plan_id(1538)
+///     .os_id(1001)
+///     .keypair_id(42)
+///     .cycle(BillingCycle::Monthly);
+/// let order = vm.create_with(&request).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CreateVmRequest {
+    pub(crate) name: String,
+    pub(crate) description: Option<String>,
+    pub(crate) username: String,
+    pub(crate) password: Secret,
+    pub(crate) plan_id: Option<u32>,
+    pub(crate) os: Option<OsSelection>,
+    pub(crate) keypair_id: Option<u32>,
+    pub(crate) cycle: BillingCycle,
+    pub(crate) use_credit_card: bool,
+    pub(crate) promocode: Option<String>,
+}
+
+/// OS templates are ordered by name, an id has to be looked up first.
+#[derive(Debug, Clone)]
+pub(crate) enum OsSelection {
+    Id(u32),
+    Name(String),
+}
+
+impl CreateVmRequest {
+    /// A monthly billed VM, paid without a credit card.
+    pub fn new(
+        name: impl Into<String>,
+        username: impl Into<String>,
+        password: impl Into<Secret>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            description: None,
+            username: username.into(),
+            password: password.into(),
+            plan_id: None,
+            os: None,
+            keypair_id: None,
+            cycle: BillingCycle::Monthly,
+            use_credit_card: false,
+            promocode: None,
+        }
+    }
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+    pub fn plan_id(mut self, id: u32) -> Self {
+        self.plan_id = Some(id);
+        self
+    }
+    pub fn plan(self, plan: &PlanResource) -> Self {
+        self.plan_id(plan.id)
+    }
+    /// An OS id of the plan. It is resolved to a name when the order is placed.
+    pub fn os_id(mut self, id: u32) -> Self {
+        self.os = Some(OsSelection::Id(id));
+        self
+    }
+    pub fn os_name(mut self, name: impl Into<String>) -> Self {
+        self.os = Some(OsSelection::Name(name.into()));
+        self
+    }
+    pub fn os(self, os: &OsResource) -> Self {
+        self.os_name(os.name.clone())
+    }
+    pub fn keypair_id(mut self, id: u32) -> Self {
+        self.keypair_id = Some(id);
+        self
+    }
+    pub fn keypair(self, keypair: &KeypairResource) -> Self {
+        self.keypair_id(keypair.id)
+    }
+    pub fn cycle(mut self, cycle: BillingCycle) -> Self {
+        self.cycle = cycle;
+        self
+    }
+    pub fn use_credit_card(mut self, use_credit_card: bool) -> Self {
+        self.use_credit_card = use_credit_card;
+        self
+    }
+    pub fn promocode(mut self, promocode: impl Into<String>) -> Self {
+        self.promocode = Some(promocode.into());
+        self
+    }
+    /// Checks every field and reports all problems in a single [`Error::Validation`](crate::Error::Validation).
+    ///
+    /// Only rules of the systems the VM is set up with are checked, the
+    /// portal doesn't document its own:
+    ///
+    /// - The VM name becomes its host name, so it is a host name label
+    ///   (RFC 1123, section 2.1): 1 to 63 ASCII letters, digits and hyphens,
+    ///   not starting or ending with a hyphen.
+    /// - The username is created with useradd(8), so it matches its default
+    ///   `NAME_REGEX`, `^[a-z_][a-z0-9_-]*$`, has at most 32 characters and
+    ///   is not a system account that exists on every image, like `root`.
+    /// - The password is set with chpasswd(8), which reads one
+    ///   `user:password` per line, so it is not empty and has no line break.
+    /// - The promo code, when given, is not blank.
+    /// - A plan, an OS and a keypair are selected.
+    pub fn validate(&self) -> Result<(), crate::Error> {
+        let mut problems = Vec::new();
+        validate_name(&self.name, &mut problems);
+        validate_username(&self.username, &mut problems);
+        validate_password(self.password.expose(), &mut problems);
+        if let Some(promocode) = &self.promocode {
+            if promocode.trim().is_empty() {
+                problems.push("promocode: must not be blank".to_string());
+            }
+        }
+        if self.plan_id.is_none() {
+            problems.push("plan: is required".to_string());
+        }
+        match &self.os {
+            None => problems.push("os: is required".to_string()),
+            Some(OsSelection::Name(name)) if name.trim().is_empty() => {
+                problems.push("os: name must not be empty".to_string())
+            }
+            Some(_) => {}
+        }
+        if self.keypair_id.is_none() {
+            problems.push("keypair: is required".to_string());
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(crate::Error::Validation(problems))
+        }
+    }
+    pub(crate) fn from_options(
+        name: String,
+        description: Option<String>,
+        username: String,
+        password: Secret,
+        opts: &VirtualMachineOptions,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            username,
+            password,
+            plan_id: Some(opts.plan.id),
+            os: Some(OsSelection::Name(opts.os.name.clone())),
+            keypair_id: Some(opts.keypair.id),
+            cycle: opts.cycle.clone(),
+            use_credit_card: opts.use_credit_card,
+            promocode: opts.promocode.clone(),
+        }
+    }
+}
+
+fn validate_name(name: &str, problems: &mut Vec<String>) {
+    let valid_chars = name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-');
+    if name.is_empty() || name.len() > NAME_MAX_LEN {
+        problems.push(format!("name: must be 1 to {} characters", NAME_MAX_LEN));
+    } else if !valid_chars || name.starts_with('-') || name.ends_with('-') {
+        problems.push(
+            "name: must be letters, digits and `-`, and not start or end with `-`".to_string(),
+        );
+    }
+}
+
+fn validate_username(username: &str, problems: &mut Vec<String>) {
+    let valid_chars = username
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-');
+    let valid_start = username.starts_with(|c: char| c.is_ascii_lowercase() || c == '_');
+    if username.is_empty() || username.len() > USERNAME_MAX_LEN {
+        problems.push(format!(
+            "username: must be 1 to {} characters",
+            USERNAME_MAX_LEN
+        ));
+    } else if !valid_start || !valid_chars {
+        problems.push(
+            "username: must be lowercase letters, digits, `_` or `-` and start with a letter or `_`"
+                .to_string(),
+        );
+    } else if RESERVED_USERNAMES.contains(&username) {
+        problems.push(format!("username: `{}` is a system account", username));
+    }
+}
+
+fn validate_password(password: &str, problems: &mut Vec<String>) {
+    if password.is_empty() {
+        problems.push("password: is required".to_string());
+    } else if password.contains(['\n', '\r']) {
+        problems.push("password: must not contain a line break".to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request() -> CreateVmRequest {
+        CreateVmRequest::new("thorin-os2", "thethorin", "SpeakFriendAndEnter123")
+            .plan_id(1538)
+            .os_id(1001)
+            .keypair_id(42)
+    }
+
+    fn problems(request: &CreateVmRequest) -> Vec<String> {
+        match request.validate() {
+            Ok(()) => Vec::new(),
+            Err(crate::Error::Validation(problems)) => problems,
+            Err(e) => panic!("unexpected error: {}", e),
+        }
+    }
+
+    fn assert_rejected(request: CreateVmRequest, field: &str) {
+        let problems = problems(&request);
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert!(problems[0].starts_with(field), "{:?}", problems);
+    }
+
+    #[test]
+    fn accepts_a_complete_request() {
+        assert!(request().validate().is_ok());
+        let request = request().description("").promocode("NEOLITE-2024");
+        assert!(request.validate().is_ok());
+    }
+
+    #[test]
+    fn checks_the_name_as_a_host_name_label() {
+        for name in ["Thorin", "2thorin", "a", &"a".repeat(63)] {
+            let mut request = request();
+            request.name = name.to_string();
+            assert!(request.validate().is_ok(), "{}", name);
+        }
+        for name in [
+            "",
+            &"a".repeat(64),
+            "-thorin",
+            "thorin-",
+            "thorin_os",
+            "thorin.os",
+        ] {
+            let mut request = request();
+            request.name = name.to_string();
+            assert_rejected(request, "name:");
+        }
+    }
+
+    #[test]
+    fn checks_the_username_as_useradd_does() {
+        for username in ["_svc", "t", "thorin-2", &"t".repeat(32)] {
+            let request = CreateVmRequest::new("thorin", username, "mellon");
+            let request = request.plan_id(1).os_id(1).keypair_id(1);
+            assert!(request.validate().is_ok(), "{}", username);
+        }
+        for username in [
+            "",
+            &"t".repeat(33),
+            "Thorin",
+            "2thorin",
+            "-thorin",
+            "tho rin",
+        ] {
+            let request = CreateVmRequest::new("thorin", username, "mellon");
+            assert_rejected(request.plan_id(1).os_id(1).keypair_id(1), "username:");
+        }
+    }
+
+    #[test]
+    fn rejects_system_accounts() {
+        for username in RESERVED_USERNAMES {
+            let request = CreateVmRequest::new("thorin", *username, "mellon");
+            assert_rejected(request.plan_id(1).os_id(1).keypair_id(1), "username:");
+        }
+    }
+
+    #[test]
+    fn checks_the_password_for_chpasswd() {
+        let with = |password: &str| {
+            CreateVmRequest::new("thorin", "thethorin", password)
+                .plan_id(1)
+                .os_id(1)
+                .keypair_id(1)
+        };
+        assert!(with("a:b c").validate().is_ok());
+        assert_rejected(with(""), "password:");
+        assert_rejected(with("mellon\nroot:x"), "password:");
+        assert_rejected(with("mellon\r"), "password:");
+    }
+
+    #[test]
+    fn rejects_a_blank_promocode() {
+        assert_rejected(request().promocode(" "), "promocode:");
+    }
+
+    #[test]
+    fn requires_a_plan_an_os_and_a_keypair() {
+        let request = CreateVmRequest::new("thorin", "thethorin", "mellon");
+        assert_eq!(
+            problems(&request),
+            [
+                "plan: is required",
+                "os: is required",
+                "keypair: is required"
+            ]
+        );
+        assert_rejected(self::request().os_name(" "), "os:");
+    }
+
+    #[test]
+    fn reports_every_problem_at_once() {
+        let request = CreateVmRequest::new("-", "root", "").promocode("");
+        let problems = problems(&request);
+        let fields: Vec<&str> = problems
+            .iter()
+            .map(|problem| problem.split(':').next().unwrap())
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                "name",
+                "username",
+                "password",
+                "promocode",
+                "plan",
+                "os",
+                "keypair"
+            ]
+        );
+        let error = request.validate().unwrap_err();
+        assert_eq!(error.to_string().matches("; ").count(), 6);
+    }
+}
@@ -1,5 +1,7 @@
 use std::sync::Arc;
 
+use futures::{future, Stream, TryStreamExt};
+use reqwest::Method;
 use serde::{Deserialize, Serialize};
 use serde_json as json;
 
@@ -8,19 +10,20 @@ use crate::{
     client::Client,
     keypair::KeypairResource,
     lite::BillingResource,
-    plan::{Billing, PlanResource},
+    plan::{BillingCycle, PlanResource},
+    secret::Secret,
 };
 
 pub struct RestoreVirtualMachineOptions {
     pub plan: PlanResource,
     pub keypair: KeypairResource,
-    pub billing: Billing,
+    pub cycle: BillingCycle,
     pub use_credit_card: bool,
     pub promocode: Option<String>,
 }
 
 pub struct SnapshotOpts {
-    pub billing: Billing,
+    pub cycle: BillingCycle,
     pub use_credit_card: bool,
     pub promocode: Option<String>,
 }
@@ -33,6 +36,7 @@ impl Snapshot {
     pub fn new(client: Arc<Client>) -> Self {
         Self { client }
     }
+    #[tracing::instrument(skip(self))]
     pub async fn list(&self) -> Result<Vec<SnapshotResource>, crate::Error> {
         let mut snapshots: Vec<SnapshotResource> = Vec::new();
 
@@ -52,6 +56,20 @@ impl Snapshot {
         }
         Ok(snapshots)
     }
+    /// Streams snapshots as they arrive.
+    pub fn stream(
+        &self,
+    ) -> impl Stream<Item = Result<SnapshotResource, crate::Error>> + Send + 'static {
+        Account::new(Arc::clone(&self.client))
+            .stream_snapshot()
+            // Skips terminated vm. It can't be accessed
+            .try_filter(|account| future::ready(account.status != AccountStatus::Terminated))
+            .map_ok(|account| SnapshotResource {
+                id: account.id,
+                name: account.extra_details.name,
+            })
+    }
+    #[tracing::instrument(skip_all, fields(vm_id = vm_id, name = %name))]
     pub async fn create(
         &self,
         vm_id: u32,
@@ -66,7 +84,7 @@ impl Snapshot {
         let body = json::json!({
             "name": &name,
             "description": &description,
-            "cycle": opts.billing.cycle,
+            "cycle": opts.cycle.as_str(),
             "pay_invoice_with_cc": use_cc,
             "promocode": opts.promocode.as_deref(),
         });
@@ -75,8 +93,10 @@ impl Snapshot {
             .post(&format!("/accounts/{vm_id}/snapshot"), body)
             .await?;
         let response: BillingResource = json::from_value(response)?;
+        let response = response.with_client(Arc::clone(&self.client));
         Ok(response)
     }
+    #[tracing::instrument(skip(self))]
     pub async fn get(&self, id: u32) -> Result<SnapshotResource, crate::Error> {
         let account = Account::new(Arc::clone(&self.client));
         let account = account.get_snapshot(id).await?;
@@ -86,23 +106,31 @@ impl Snapshot {
         };
         Ok(snapshot)
     }
+    #[tracing::instrument(skip(self))]
     pub async fn delete(&self, id: u32) -> Result<(), crate::Error> {
         self.client.delete(&format!("/snapshots/{}", id)).await?;
         Ok(())
     }
+    #[tracing::instrument(skip(self))]
     pub async fn restore(&self, id: u32) -> Result<(), crate::Error> {
+        // A repeated restore rolls the VM back again.
         self.client
-            .put(&format!("/snapshots/accounts/{id}/restore"))
+            .send_once(
+                Method::PUT,
+                &format!("/snapshots/accounts/{id}/restore"),
+                None,
+            )
             .await?;
         Ok(())
     }
+    #[tracing::instrument(skip_all, fields(snapshot_id = snapshot_id, name = %name))]
     pub async fn restore_with(
         &self,
         snapshot_id: u32,
         name: String,
         description: Option<String>,
         username: String,
-        password: String,
+        password: impl Into<Secret>,
         opts: &RestoreVirtualMachineOptions,
     ) -> Result<BillingResource, crate::Error> {
         let use_cc = match opts.use_credit_card {
@@ -111,12 +139,12 @@ impl Snapshot {
         };
         let body = json::json!({
             "ssh_and_console_user": &username,
-            "console_password": &password,
+            "console_password": password.into().expose(),
             "vm_name": &name,
             "description": &description,
             "product_id": opts.plan.id,
             "keypair_id": opts.keypair.id,
-            "cycle": opts.billing.cycle,
+            "cycle": opts.cycle.as_str(),
             "pay_invoice_with_cc": use_cc,
             "promocode": opts.promocode,
         });
@@ -125,6 +153,7 @@ impl Snapshot {
             .post(&format!("/snapshots/accounts/{snapshot_id}/create"), body)
             .await?;
         let response: BillingResource = json::from_value(response)?;
+        let response = response.with_client(Arc::clone(&self.client));
         Ok(response)
     }
 }
@@ -1,27 +1,34 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, fmt, net::IpAddr, sync::Arc};
 
-use serde::{Deserialize, Serialize};
+use futures::{future, stream, Stream, StreamExt, TryStreamExt};
+use reqwest::Method;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json as json;
 
-use super::account::{Account, AccountStatus};
+use super::account::{Account, AccountResource, AccountStatus};
 use crate::{
     client::Client,
     keypair::KeypairResource,
     lite::BillingResource,
-    os::OsResource,
-    plan::{Billing, PlanResource},
+    os::{Os, OsResource},
+    plan::{BillingCycle, Plan, PlanResource},
+    preflight::{self, PreflightReport},
+    quote::{ChangeQuote, Quote},
+    request::{CreateVmRequest, OsSelection},
+    secret::Secret,
+    wait::{self, WaitOptions},
 };
 
 pub struct VirtualMachineOptions {
     pub plan: PlanResource,
     pub keypair: KeypairResource,
     pub os: OsResource,
-    pub billing: Billing,
+    pub cycle: BillingCycle,
     pub use_credit_card: bool,
     pub promocode: Option<String>,
 }
 
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 /// Terminated is not on the list because termindated VM can't be accessed.
 pub enum VirtualMachineStatus {
     Active,
@@ -39,125 +46,374 @@ impl VirtualMachineStatus {
     }
 }
 
+/// Number of `vm-details` requests [`VirtualMachine::list`] runs at once.
+pub const DEFAULT_CONCURRENCY: usize = 8;
+
+impl From<VirtualMachineStatus> for AccountStatus {
+    fn from(status: VirtualMachineStatus) -> Self {
+        match status {
+            VirtualMachineStatus::Active => AccountStatus::Active,
+            VirtualMachineStatus::Pending => AccountStatus::Pending,
+            VirtualMachineStatus::Suspended => AccountStatus::Suspended,
+        }
+    }
+}
+
 pub struct VirtualMachine {
     client: Arc<Client>,
+    concurrency: usize,
 }
 
 impl VirtualMachine {
     pub fn new(client: Arc<Client>) -> Self {
-        Self { client }
+        Self {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
     }
+    /// Sets how many VMs are fetched in parallel when listing.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+    #[tracing::instrument(skip(self))]
     pub async fn list(&self) -> Result<Vec<VirtualMachineResource>, crate::Error> {
-        let mut vms: Vec<VirtualMachineResource> = Vec::new();
-
         let account = Account::new(Arc::clone(&self.client));
         let accounts = account.list().await?;
-
-        for account in accounts {
-            // Skips terminated vm. It can't be accessed
-            if account.status == AccountStatus::Terminated {
-                continue;
-            }
-            let vm = self.get(account.id).await?;
-            vms.push(vm);
-        }
-        Ok(vms)
+        self.get_many(accounts).await
     }
+    #[tracing::instrument(skip(self))]
     pub async fn list_with_status(
         &self,
         status: VirtualMachineStatus,
     ) -> Result<Vec<VirtualMachineResource>, crate::Error> {
-        let mut vms: Vec<VirtualMachineResource> = Vec::new();
-
-        let status = match status {
-            VirtualMachineStatus::Active => AccountStatus::Active,
-            VirtualMachineStatus::Pending => AccountStatus::Pending,
-            VirtualMachineStatus::Suspended => AccountStatus::Suspended,
-        };
         let account = Account::new(Arc::clone(&self.client));
-        let accounts = account.list_with_status(status).await?;
-
-        for account in accounts {
+        let accounts = account.list_with_status(status.into()).await?;
+        self.get_many(accounts).await
+    }
+    /// Streams VMs as their details arrive, in account order.
+    ///
+    /// Dropping the stream stops further requests.
+    pub fn stream(
+        &self,
+    ) -> impl Stream<Item = Result<VirtualMachineResource, crate::Error>> + Send + 'static {
+        let client = Arc::clone(&self.client);
+        Account::new(Arc::clone(&self.client))
+            .stream()
             // Skips terminated vm. It can't be accessed
-            if account.status == AccountStatus::Terminated {
-                continue;
+            .try_filter(|account| future::ready(account.status != AccountStatus::Terminated))
+            .map_ok(move |account| {
+                let vm = VirtualMachine::new(Arc::clone(&client));
+                async move { vm.get_with_account(account).await }
+            })
+            .try_buffered(self.concurrency)
+    }
+    /// Like [`VirtualMachine::list`], but a VM whose details fail to load
+    /// doesn't fail the whole listing. Its error is reported instead.
+    #[tracing::instrument(skip(self))]
+    pub async fn list_partial(&self) -> Result<VirtualMachineList, crate::Error> {
+        let account = Account::new(Arc::clone(&self.client));
+        let accounts = account.list().await?;
+        Ok(self.get_each(accounts).await)
+    }
+    #[tracing::instrument(skip(self))]
+    pub async fn list_partial_with_status(
+        &self,
+        status: VirtualMachineStatus,
+    ) -> Result<VirtualMachineList, crate::Error> {
+        let account = Account::new(Arc::clone(&self.client));
+        let accounts = account.list_with_status(status.into()).await?;
+        Ok(self.get_each(accounts).await)
+    }
+    /// Lists VMs from the account data alone, without live stats.
+    ///
+    /// This costs a single request, while [`VirtualMachine::list`] also
+    /// fetches `vm-details` for every VM.
+    #[tracing::instrument(skip(self))]
+    pub async fn list_summary(&self) -> Result<Vec<VirtualMachineSummary>, crate::Error> {
+        let account = Account::new(Arc::clone(&self.client));
+        let accounts = account.list().await?;
+        let summaries = accounts
+            .into_iter()
+            .filter_map(|account| VirtualMachineSummary::from_account(account, &self.client))
+            .collect();
+        Ok(summaries)
+    }
+    /// Fetches the details of every accessible account, keeping their order.
+    async fn get_many(
+        &self,
+        accounts: Vec<AccountResource>,
+    ) -> Result<Vec<VirtualMachineResource>, crate::Error> {
+        stream::iter(accessible(accounts))
+            .map(|account| self.get_with_account(account))
+            .buffered(self.concurrency)
+            .try_collect()
+            .await
+    }
+    async fn get_each(&self, accounts: Vec<AccountResource>) -> VirtualMachineList {
+        let results: Vec<(u32, Result<VirtualMachineResource, crate::Error>)> =
+            stream::iter(accessible(accounts))
+                .map(|account| async move { (account.id, self.get_with_account(account).await) })
+                .buffered(self.concurrency)
+                .collect()
+                .await;
+
+        let mut list = VirtualMachineList::default();
+        for (id, result) in results {
+            match result {
+                Ok(vm) => list.vms.push(vm),
+                Err(e) => {
+                    tracing::warn!("failed to get VM {}: {}", id, e);
+                    list.errors.insert(id, e);
+                }
             }
-            let vm = self.get(account.id).await?;
-            vms.push(vm);
         }
-        Ok(vms)
+        list
     }
+    #[tracing::instrument(skip(self))]
     pub async fn get(&self, id: u32) -> Result<VirtualMachineResource, crate::Error> {
+        let account = Account::new(Arc::clone(&self.client));
+        let (details, account) = future::try_join(self.details(id), account.get(id)).await?;
+        Ok(VirtualMachineResource::new(details, account, &self.client))
+    }
+    /// Polls VM `id` until `done` accepts it.
+    ///
+    /// A VM that is still being provisioned counts as not done.
+    #[tracing::instrument(skip(self, done))]
+    pub async fn wait_for(
+        &self,
+        id: u32,
+        done: impl FnMut(&VirtualMachineResource) -> bool,
+        opts: &WaitOptions,
+    ) -> Result<VirtualMachineResource, crate::Error> {
+        let fetch = move || async move {
+            match self.get(id).await {
+                Ok(vm) => Ok(Some(vm)),
+                Err(crate::Error::NotFound { .. }) => Ok(None),
+                Err(e) => Err(e),
+            }
+        };
+        let describe = |vm: &VirtualMachineResource| {
+            format!("{}, account {}", vm.status, vm.account_status.as_str())
+        };
+        wait::poll(format!("VM {}", id), opts, fetch, done, describe).await
+    }
+    /// Polls VM `id` until it is in `state`.
+    pub async fn wait_for_state(
+        &self,
+        id: u32,
+        state: PowerState,
+        opts: &WaitOptions,
+    ) -> Result<VirtualMachineResource, crate::Error> {
+        self.wait_for(id, |vm| vm.status == state, opts).await
+    }
+    async fn get_with_account(
+        &self,
+        account: AccountResource,
+    ) -> Result<VirtualMachineResource, crate::Error> {
+        let details = self.details(account.id).await?;
+        Ok(VirtualMachineResource::new(details, account, &self.client))
+    }
+    async fn details(&self, id: u32) -> Result<VmDetails, crate::Error> {
         let response = self
             .client
             .get(&format!("/accounts/{}/vm-details", id))
             .await?;
-        let response: VirtualMachineResource = json::from_value(response)?;
-        let response = response.with_client(Arc::clone(&self.client));
-        // Set `id` to use `Account ID` instead of real `VM ID`.
-        // The `Account ID` is used throughout the upstream REST API.
-        let response = response.change_id(id);
+        let response: VmDetails = json::from_value(response)?;
         Ok(response)
     }
+    #[tracing::instrument(skip_all, fields(name = %name, plan_id = opts.plan.id))]
     pub async fn create(
         &self,
         name: String,
         description: Option<String>,
         username: String,
-        password: String,
+        password: impl Into<Secret>,
         opts: &VirtualMachineOptions,
     ) -> Result<BillingResource, crate::Error> {
-        let use_cc = match opts.use_credit_card {
+        let request =
+            CreateVmRequest::from_options(name, description, username, password.into(), opts);
+        self.submit(&request).await
+    }
+    /// Validates `request`, then orders the VM.
+    ///
+    /// Nothing is sent when the request is invalid.
+    #[tracing::instrument(skip_all, fields(name = %request.name))]
+    pub async fn create_with(
+        &self,
+        request: &CreateVmRequest,
+    ) -> Result<BillingResource, crate::Error> {
+        request.validate()?;
+        self.submit(request).await
+    }
+    /// Checks that `request` would be accepted, without placing an order.
+    #[tracing::instrument(skip_all, fields(name = %request.name))]
+    pub async fn preflight(
+        &self,
+        request: &CreateVmRequest,
+    ) -> Result<PreflightReport, crate::Error> {
+        preflight::run(&self.client, request).await
+    }
+    async fn submit(&self, request: &CreateVmRequest) -> Result<BillingResource, crate::Error> {
+        let missing =
+            |field: &str| crate::Error::InvalidArgument(format!("`{}` is required", field));
+        let plan_id = request.plan_id.ok_or_else(|| missing("plan"))?;
+        let keypair_id = request.keypair_id.ok_or_else(|| missing("keypair"))?;
+        let os_name = match request.os.as_ref().ok_or_else(|| missing("os"))? {
+            OsSelection::Name(name) => name.clone(),
+            OsSelection::Id(id) => {
+                Os::new(Arc::clone(&self.client), plan_id)
+                    .get(*id)
+                    .await?
+                    .name
+            }
+        };
+        let use_cc = match request.use_credit_card {
             true => "yes",
             false => "no",
         };
         let body = json::json!({
-            "ssh_and_console_user":  &username,
-            "console_password":  &password,
-            "vm_name": &name,
-            "description": &description,
-            "product_id": opts.plan.id,
-            "select_os": &opts.os.name,
-            "keypair_id": opts.keypair.id,
-            "cycle": opts.billing.cycle,
+            "ssh_and_console_user":  &request.username,
+            "console_password": request.password.expose(),
+            "vm_name": &request.name,
+            "description": &request.description,
+            "product_id": plan_id,
+            "select_os": &os_name,
+            "keypair_id": keypair_id,
+            "cycle": request.cycle.as_str(),
             "pay_invoice_with_cc": use_cc,
-            "promocode": opts.promocode,
+            "promocode": request.promocode,
         });
         let response = self.client.post("", body).await?;
         let response: BillingResource = json::from_value(response)?;
+        let response = response.with_client(Arc::clone(&self.client));
         Ok(response)
     }
+    #[tracing::instrument(skip(self))]
     pub async fn delete(&self, id: u32) -> Result<(), crate::Error> {
         self.client.delete(&format!("/{}", id)).await?;
         Ok(())
     }
 }
 
+/// Power state of a VM as reported by the hypervisor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PowerState {
+    Running,
+    Stopped,
+    Paused,
+    Unknown(String),
+}
+
+impl PowerState {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Running => "running",
+            Self::Stopped => "stopped",
+            Self::Paused => "paused",
+            Self::Unknown(state) => state,
+        }
+    }
+}
+
+impl fmt::Display for PowerState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for PowerState {
+    fn from(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "running" => Self::Running,
+            "stopped" => Self::Stopped,
+            "paused" | "suspended" => Self::Paused,
+            _ => Self::Unknown(value.to_string()),
+        }
+    }
+}
+
+impl Serialize for PowerState {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for PowerState {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Ok(Self::from(value.as_str()))
+    }
+}
+
+/// A VM, merged from its `vm-details` and its account.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VirtualMachineResource {
-    #[serde(rename = "vmid")]
+    /// The account id. It is used throughout the upstream REST API,
+    /// unlike the hypervisor id in `vmid`.
     pub id: u32,
+    pub vmid: u32,
     pub name: String,
-    pub status: String,
+    pub description: String,
+    pub status: PowerState,
+    pub account_status: AccountStatus,
+    pub region: String,
+    pub os_name: String,
+    pub ip_addresses: Vec<IpAddr>,
+    /// Disk size in GB.
+    pub disk_size: u32,
+    pub keypair_id: u32,
     pub uptime: i32,
     pub maxdisk: i64,
     pub maxmem: i64,
-    pub mem: i32,
+    pub mem: i64,
     pub cpus: i32,
 
     #[serde(skip)]
     client: Arc<Client>,
 }
 
+/// The `vm-details` payload, before it is merged with the account.
+#[derive(Debug, Deserialize)]
+struct VmDetails {
+    vmid: u32,
+    name: String,
+    status: PowerState,
+    uptime: i32,
+    maxdisk: i64,
+    maxmem: i64,
+    mem: i64,
+    cpus: i32,
+}
+
 impl VirtualMachineResource {
-    fn with_client(mut self, client: Arc<Client>) -> Self {
-        self.client = client;
-        self
-    }
-    fn change_id(mut self, id: u32) -> Self {
-        self.id = id;
-        self
+    fn new(details: VmDetails, account: AccountResource, client: &Arc<Client>) -> Self {
+        const GIB: i64 = 1024 * 1024 * 1024;
+        let disk_size = disk_size(&account)
+            .unwrap_or_else(|_| u32::try_from(details.maxdisk / GIB).unwrap_or_default());
+        let ip_addresses = account.extra_details.ip_addresses();
+        let extra = account.extra_details;
+        Self {
+            id: account.id,
+            vmid: details.vmid,
+            name: details.name,
+            description: extra.description,
+            status: details.status,
+            account_status: account.status,
+            region: extra.region,
+            os_name: extra.osname,
+            ip_addresses,
+            disk_size,
+            keypair_id: extra.keypair_id,
+            uptime: details.uptime,
+            maxdisk: details.maxdisk,
+            maxmem: details.maxmem,
+            mem: details.mem,
+            cpus: details.cpus,
+            client: Arc::clone(client),
+        }
     }
+    #[tracing::instrument(skip(self), fields(id = self.id))]
     pub async fn change_keypair(&self, keypair_id: u32) -> Result<(), crate::Error> {
         let body = json::json!({ "keypair_id": keypair_id });
         self.client
@@ -165,6 +421,7 @@ impl VirtualMachineResource {
             .await?;
         Ok(())
     }
+    #[tracing::instrument(skip(self), fields(id = self.id))]
     pub async fn change_name(&self, name: &str) -> Result<(), crate::Error> {
         let body = json::json!({ "name": name });
         self.client
@@ -172,24 +429,111 @@ impl VirtualMachineResource {
             .await?;
         Ok(())
     }
-    pub async fn change_plan(&self, plan_id: u32) -> Result<BillingResource, crate::Error> {
-        let body = json::json!({ "new_product_id": plan_id });
+    #[tracing::instrument(skip(self), fields(id = self.id))]
+    pub async fn change_plan(
+        &self,
+        plan_id: u32,
+        cycle: BillingCycle,
+    ) -> Result<BillingResource, crate::Error> {
+        let body = json::json!({ "new_product_id": plan_id, "cycle": cycle.as_str() });
         let response = self
             .client
             .post(&format!("/accounts/{}/change-package", self.id), body)
             .await?;
         let response: BillingResource = json::from_value(response)?;
+        let response = response.with_client(Arc::clone(&self.client));
         Ok(response)
     }
+    /// Orders a disk of `size` GB in total for this VM.
+    #[tracing::instrument(skip(self), fields(id = self.id))]
     pub async fn change_storage(&self, size: u32) -> Result<BillingResource, crate::Error> {
         let body = json::json!({ "disk_size": size });
         let response = self
             .client
-            .put_with_body(&format!("/accounts/{}/storage", self.id), body)
+            .send_once(
+                Method::PUT,
+                &format!("/accounts/{}/storage", self.id),
+                Some(body),
+            )
             .await?;
         let response: BillingResource = json::from_value(response)?;
+        let response = response.with_client(Arc::clone(&self.client));
         Ok(response)
     }
+    /// Prices [`change_plan`](Self::change_plan) without ordering it.
+    ///
+    /// `included_disk` and `new_included_disk` are the disk sizes in GB
+    /// included in the current and the new plan. The API doesn't report
+    /// them, see [`PlanResource::included_disk`](crate::plan::PlanResource::included_disk).
+    /// The current disk is kept, so only the part of it beyond the disk of
+    /// the new plan is billed as additional storage.
+    #[tracing::instrument(skip(self), fields(id = self.id))]
+    pub async fn quote_change_plan(
+        &self,
+        plan_id: u32,
+        cycle: BillingCycle,
+        included_disk: u32,
+        new_included_disk: u32,
+    ) -> Result<ChangeQuote, crate::Error> {
+        let (current, disk_size) = self.current_quote(included_disk).await?;
+        let plan = Plan::new(Arc::clone(&self.client)).get_vm(plan_id).await?;
+        let new = plan.quote(cycle, disk_size.saturating_sub(new_included_disk))?;
+        Ok(ChangeQuote { current, new })
+    }
+    /// Prices [`change_storage`](Self::change_storage) without ordering it.
+    ///
+    /// `size` is the new total disk size in GB, as for `change_storage`.
+    /// `included_disk` is the disk size in GB included in the plan of this VM.
+    #[tracing::instrument(skip(self), fields(id = self.id))]
+    pub async fn quote_change_storage(
+        &self,
+        size: u32,
+        included_disk: u32,
+    ) -> Result<ChangeQuote, crate::Error> {
+        let Some(extra_disk) = size.checked_sub(included_disk) else {
+            return Err(crate::Error::InvalidArgument(format!(
+                "A disk of {} GB is smaller than the {} GB included in the plan",
+                size, included_disk
+            )));
+        };
+        let account = Account::new(Arc::clone(&self.client)).get(self.id).await?;
+        let plan = Plan::new(Arc::clone(&self.client))
+            .get_vm(account.product_id)
+            .await?;
+        let current_extra = disk_size(&account)?.saturating_sub(included_disk);
+        let current = plan.quote(account.billingcycle.clone(), current_extra)?;
+        let new = plan.quote(account.billingcycle, extra_disk)?;
+        Ok(ChangeQuote { current, new })
+    }
+    /// The quote of the current plan and the total disk size of the VM.
+    async fn current_quote(&self, included_disk: u32) -> Result<(Quote, u32), crate::Error> {
+        let account = Account::new(Arc::clone(&self.client)).get(self.id).await?;
+        let plan = Plan::new(Arc::clone(&self.client))
+            .get_vm(account.product_id)
+            .await?;
+        let disk_size = disk_size(&account)?;
+        let extra_disk = disk_size.saturating_sub(included_disk);
+        let quote = plan.quote(account.billingcycle, extra_disk)?;
+        Ok((quote, disk_size))
+    }
+    /// Re-fetches this VM until `done` accepts it, see [`VirtualMachine::wait_for`].
+    pub async fn wait_until(
+        &self,
+        done: impl FnMut(&VirtualMachineResource) -> bool,
+        opts: &WaitOptions,
+    ) -> Result<VirtualMachineResource, crate::Error> {
+        VirtualMachine::new(Arc::clone(&self.client))
+            .wait_for(self.id, done, opts)
+            .await
+    }
+    /// Re-fetches this VM until it is in `state`, for example after [`start`](Self::start).
+    pub async fn wait_for_state(
+        &self,
+        state: PowerState,
+        opts: &WaitOptions,
+    ) -> Result<VirtualMachineResource, crate::Error> {
+        self.wait_until(|vm| vm.status == state, opts).await
+    }
     pub async fn start(&self) -> Result<(), crate::Error> {
         self.change_state("start").await?;
         Ok(())
@@ -214,17 +558,230 @@ impl VirtualMachineResource {
         self.change_state("stop").await?;
         Ok(())
     }
+    #[tracing::instrument(skip_all, fields(id = self.id, os = %os.name))]
     pub async fn rebuild(&self, os: &OsResource) -> Result<(), crate::Error> {
         let body = json::json!({ "name": os.name });
         self.client
-            .put_with_body(&format!("/accounts/{}/rebuild", self.id), body)
+            .send_once(
+                Method::PUT,
+                &format!("/accounts/{}/rebuild", self.id),
+                Some(body),
+            )
             .await?;
         Ok(())
     }
+    #[tracing::instrument(skip(self), fields(id = self.id))]
     async fn change_state(&self, state: &str) -> Result<(), crate::Error> {
-        self.client
-            .put(&format!("/accounts/{}/vm-state/{}", self.id, state))
-            .await?;
+        let path = format!("/accounts/{}/vm-state/{}", self.id, state);
+        // A reset reboots the VM again when repeated.
+        if state == "reset" {
+            self.client.send_once(Method::PUT, &path, None).await?;
+        } else {
+            self.client.put(&path).await?;
+        }
         Ok(())
     }
 }
+
+fn disk_size(account: &AccountResource) -> Result<u32, crate::Error> {
+    let disk_size = &account.extra_details.disk_size;
+    disk_size.trim().parse().map_err(|_| {
+        crate::Error::Internal(format!(
+            "Account {} has an invalid disk size `{}`",
+            account.id, disk_size
+        ))
+    })
+}
+
+// Skips terminated vm. It can't be accessed
+fn accessible(accounts: Vec<AccountResource>) -> Vec<AccountResource> {
+    accounts
+        .into_iter()
+        .filter(|account| account.status != AccountStatus::Terminated)
+        .collect()
+}
+
+/// VMs that loaded, and the errors of those that didn't.
+#[derive(Debug, Default)]
+pub struct VirtualMachineList {
+    pub vms: Vec<VirtualMachineResource>,
+    /// Errors keyed by account id.
+    pub errors: BTreeMap<u32, crate::Error>,
+}
+
+impl VirtualMachineList {
+    /// Whether every VM loaded.
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+/// A VM as described by its account, see [`VirtualMachine::list_summary`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VirtualMachineSummary {
+    /// The account id, as used by [`VirtualMachine::get`].
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub status: VirtualMachineStatus,
+    pub product_id: u32,
+    pub product_name: String,
+    pub region: String,
+    pub os_name: String,
+
+    #[serde(skip)]
+    client: Arc<Client>,
+}
+
+impl VirtualMachineSummary {
+    fn from_account(account: AccountResource, client: &Arc<Client>) -> Option<Self> {
+        let status = match account.status {
+            AccountStatus::Active => VirtualMachineStatus::Active,
+            AccountStatus::Pending => VirtualMachineStatus::Pending,
+            AccountStatus::Suspended => VirtualMachineStatus::Suspended,
+            // Skips terminated vm. It can't be accessed
+            AccountStatus::Terminated => return None,
+        };
+        Some(Self {
+            id: account.id,
+            name: account.extra_details.name,
+            description: account.extra_details.description,
+            status,
+            product_id: account.product_id,
+            product_name: account.product_name,
+            region: account.extra_details.region,
+            os_name: account.extra_details.osname,
+            client: Arc::clone(client),
+        })
+    }
+    /// Fetches the live details of this VM.
+    pub async fn details(&self) -> Result<VirtualMachineResource, crate::Error> {
+        VirtualMachine::new(Arc::clone(&self.client))
+            .get(self.id)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details() -> VmDetails {
+        json::from_value(json::json!({
+            "vmid": 184,
+            "name": "thorin-os2",
+            "status": "running",
+            "uptime": 3600,
+            "maxdisk": 32212254720_i64,
+            "maxmem": 2147483648_i64,
+            "mem": 1073741824,
+            "cpus": 2,
+        }))
+        .unwrap()
+    }
+
+    fn account_with(disk_size: &str, other: json::Value) -> AccountResource {
+        let mut extra_details = json::json!({
+            "region": "jkt",
+            "region_label": "Jakarta",
+            "description": "Thorin Virtual Machine",
+            "name": "thorin-os2",
+            "tenant_id": null,
+            "ciuser": "thethorin",
+            "cipassword": "secret",
+            "neosshkey_id": 7,
+            "sshkeys": "",
+            "osname": "ubuntu-22.04",
+            "disk_size": disk_size,
+        });
+        extra_details
+            .as_object_mut()
+            .unwrap()
+            .extend(other.as_object().unwrap().clone());
+        json::from_value(json::json!({
+            "account_id": "1001",
+            "domain": "",
+            "status": "Suspended",
+            "billingcycle": "Monthly",
+            "recurring_amount": 120000,
+            "extra_details": extra_details,
+            "product_id": 1538,
+            "product_name": "NEO Lite Pro 1.1",
+            "description": "",
+            "category_id": 30,
+            "category_name": "NEO Lite Pro",
+            "last_invoice": {
+                "id": 1,
+                "paid_id": 0,
+                "status": "Paid",
+                "invoice_type": "new",
+            },
+        }))
+        .unwrap()
+    }
+
+    fn ip(address: &str) -> IpAddr {
+        address.parse().unwrap()
+    }
+
+    #[test]
+    fn parses_power_states() {
+        let cases = [
+            ("running", PowerState::Running),
+            ("Running", PowerState::Running),
+            ("STOPPED", PowerState::Stopped),
+            ("paused", PowerState::Paused),
+            ("suspended", PowerState::Paused),
+            ("migrating", PowerState::Unknown("migrating".to_string())),
+        ];
+        for (value, state) in cases {
+            assert_eq!(PowerState::from(value), state, "{}", value);
+            let parsed: PowerState = json::from_value(json::json!(value)).unwrap();
+            assert_eq!(parsed, state, "{}", value);
+        }
+        assert_eq!(PowerState::from("Migrating").to_string(), "Migrating");
+        assert_eq!(json::to_value(PowerState::Stopped).unwrap(), "stopped");
+    }
+
+    #[test]
+    fn merges_vm_details_with_the_account() {
+        let client = Arc::new(Client::default());
+        let account = account_with("20", json::json!({ "hostname": "thorin" }));
+        let vm = VirtualMachineResource::new(details(), account, &client);
+
+        assert_eq!(vm.id, 1001);
+        assert_eq!(vm.vmid, 184);
+        assert_eq!(vm.name, "thorin-os2");
+        assert_eq!(vm.description, "Thorin Virtual Machine");
+        assert_eq!(vm.status, PowerState::Running);
+        assert_eq!(vm.account_status, AccountStatus::Suspended);
+        assert_eq!(vm.region, "jkt");
+        assert_eq!(vm.os_name, "ubuntu-22.04");
+        assert!(vm.ip_addresses.is_empty());
+        assert_eq!(vm.disk_size, 20);
+        assert_eq!(vm.keypair_id, 7);
+        assert_eq!(vm.cpus, 2);
+
+        // Without a usable disk size, the hypervisor's is used.
+        let account = account_with("", json::json!({}));
+        let vm = VirtualMachineResource::new(details(), account, &client);
+        assert_eq!(vm.disk_size, 30);
+    }
+
+    #[test]
+    fn finds_ip_addresses_in_any_extra_detail() {
+        let account = account_with(
+            "20",
+            json::json!({
+                "ip": "103.150.3.233, 10.0.0.2",
+                "ipv6": ["2001:db8::1", "not an address"],
+                "hostname": "thorin",
+                "vlan": 12,
+            }),
+        );
+        assert_eq!(
+            account.extra_details.ip_addresses(),
+            [ip("103.150.3.233"), ip("10.0.0.2"), ip("2001:db8::1")]
+        );
+    }
+}
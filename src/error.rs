@@ -1,31 +1,229 @@
+use std::{fmt, time::Duration};
+
+use reqwest::{Method, StatusCode};
 use serde_json as json;
 
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
-    #[error("Internal error")]
+    #[error("Internal error: {0}")]
     Internal(String),
 
-    #[error("{0}")]
-    NotFound(String),
-
-    #[error("{0}")]
-    PermissionDenied(String),
+    /// A missing resource. `response` is the `404 Not Found` response, when
+    /// the API reported it rather than a lookup in a listing.
+    #[error("{message}")]
+    NotFound {
+        message: String,
+        response: Option<Box<ApiError>>,
+    },
 
     #[error("{0}")]
     InvalidArgument(String),
 
-    #[error("{0}")]
-    AlreadyExists(String),
+    /// Every problem found in a request before it was sent.
+    #[error("Invalid request: {}", .0.join("; "))]
+    Validation(Vec<String>),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Unauthorized. {0}")]
+    Unauthorized(Box<ApiError>),
+
+    /// `403 Forbidden`.
+    #[error("Permission denied. {0}")]
+    PermissionDenied(Box<ApiError>),
+
+    /// `409 Conflict`.
+    #[error("Already exists. {0}")]
+    AlreadyExists(Box<ApiError>),
+
+    #[error("Rate limited. {0}")]
+    RateLimited(Box<ApiError>),
+
+    #[error("Server error. {0}")]
+    Server(Box<ApiError>),
+
+    /// Any other unsuccessful response, such as `400 Bad Request`.
+    #[error("Request failed. {0}")]
+    Api(Box<ApiError>),
+
+    #[error("Transport error: {0}")]
+    Transport(#[source] reqwest::Error),
+
+    #[error("Request timed out: {0}")]
+    Timeout(#[source] reqwest::Error),
+
+    /// A wait gave up before the resource reached the expected state.
+    #[error("Timed out after {elapsed:?} waiting for {resource}. Last state: {last_state}")]
+    WaitTimeout {
+        resource: String,
+        elapsed: Duration,
+        /// The state observed by the last poll.
+        last_state: String,
+    },
+
+    #[error("Failed to decode response: {source}")]
+    Decode {
+        #[source]
+        source: json::Error,
+        /// Response body with secrets redacted, when the failure happened
+        /// while reading a response.
+        body: Option<String>,
+    },
+}
+
+/// An unsuccessful response from the NEO Lite REST API.
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub method: Method,
+    pub path: String,
+    /// The `code` field of the response envelope, if the body had one.
+    pub code: Option<i32>,
+    /// The `message` field of the response envelope, if the body had one.
+    pub message: Option<String>,
+    /// Response body with secrets redacted, so neither `Display` nor `Debug`
+    /// can leak them.
+    pub body: String,
+    /// Parsed `Retry-After` header.
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}. Status: {}", self.method, self.path, self.status)?;
+        if let Some(code) = self.code {
+            write!(f, ". Code: {}", code)?;
+        }
+        if let Some(message) = &self.message {
+            return write!(f, ". Message: {}", message);
+        }
+        write!(f, ". Body: {}", self.body)
+    }
+}
+
+impl Error {
+    pub(crate) fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound {
+            message: message.into(),
+            response: None,
+        }
+    }
+    /// Replaces the message of a [`Error::NotFound`], keeping its response.
+    /// Other errors are returned unchanged.
+    pub(crate) fn describe_not_found(self, message: impl Into<String>) -> Self {
+        match self {
+            Self::NotFound { response, .. } => Self::NotFound {
+                message: message.into(),
+                response,
+            },
+            e => e,
+        }
+    }
+    /// Builds the error matching the status of an unsuccessful response.
+    pub(crate) fn from_response(error: ApiError) -> Self {
+        let error = Box::new(error);
+        match error.status {
+            StatusCode::NOT_FOUND => Self::NotFound {
+                message: format!("Resource not found. {}", error),
+                response: Some(error),
+            },
+            StatusCode::UNAUTHORIZED => Self::Unauthorized(error),
+            StatusCode::FORBIDDEN => Self::PermissionDenied(error),
+            StatusCode::CONFLICT => Self::AlreadyExists(error),
+            StatusCode::TOO_MANY_REQUESTS => Self::RateLimited(error),
+            status if status.is_server_error() => Self::Server(error),
+            _ => Self::Api(error),
+        }
+    }
+    /// HTTP status of the response that caused this error, if any.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::NotFound { .. } => Some(StatusCode::NOT_FOUND),
+            Self::Transport(e) | Self::Timeout(e) => e.status(),
+            _ => self.api_error().map(|e| e.status),
+        }
+    }
+    /// Details of the unsuccessful response, if this error came from one.
+    pub fn api_error(&self) -> Option<&ApiError> {
+        match self {
+            Self::NotFound {
+                response: Some(e), ..
+            } => Some(e),
+            Self::Unauthorized(e)
+            | Self::PermissionDenied(e)
+            | Self::AlreadyExists(e)
+            | Self::RateLimited(e)
+            | Self::Server(e)
+            | Self::Api(e) => Some(e),
+            _ => None,
+        }
+    }
+    /// Whether sending the same request again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::RateLimited(_) | Self::Server(_) | Self::Timeout(_) => true,
+            Self::Transport(e) => e.is_connect() || e.is_request() || e.is_body(),
+            _ => false,
+        }
+    }
+    /// Delay requested by the server before retrying.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.api_error().and_then(|e| e.retry_after)
+    }
 }
 
 impl std::convert::From<json::Error> for Error {
     fn from(err: json::Error) -> Self {
-        Error::InvalidArgument(format!("failed to parse json: {}", err))
+        Error::Decode {
+            source: err,
+            body: None,
+        }
     }
 }
 
 impl std::convert::From<reqwest::Error> for Error {
     fn from(err: reqwest::Error) -> Self {
-        Error::InvalidArgument(err.to_string())
+        if err.is_timeout() {
+            Error::Timeout(err)
+        } else {
+            Error::Transport(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn response(status: StatusCode) -> ApiError {
+        ApiError {
+            status,
+            method: Method::GET,
+            path: "/accounts/1".to_string(),
+            code: Some(i32::from(status.as_u16())),
+            message: None,
+            body: r#"{"success": false}"#.to_string(),
+            retry_after: None,
+        }
+    }
+
+    #[test]
+    fn maps_statuses_to_variants() {
+        let error = Error::from_response(response(StatusCode::FORBIDDEN));
+        assert!(matches!(error, Error::PermissionDenied(_)));
+        let error = Error::from_response(response(StatusCode::CONFLICT));
+        assert!(matches!(error, Error::AlreadyExists(_)));
+    }
+
+    #[test]
+    fn not_found_keeps_the_response() {
+        let error = Error::from_response(response(StatusCode::NOT_FOUND))
+            .describe_not_found("Account not found");
+        assert_eq!(error.to_string(), "Account not found");
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+        let response = error.api_error().unwrap();
+        assert_eq!(response.path, "/accounts/1");
+        assert_eq!(response.code, Some(404));
     }
 }
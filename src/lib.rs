@@ -4,11 +4,23 @@ pub mod client;
 pub mod config;
 pub mod domain;
 pub mod error;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod money;
+mod redact;
+pub mod retry;
+pub mod secret;
+pub mod transport;
+pub mod wait;
 
 pub use error::Error;
+pub use money::Money;
+pub use secret::Secret;
 
+#[cfg(feature = "keygen")]
+pub use domain::keygen;
 pub use domain::{
-    keypair, lite,
-    products::{ip, os, plan},
-    snapshot, vm,
+    account, keypair, lite, preflight,
+    products::{ip, os, plan, quote},
+    report, request, snapshot, vm,
 };
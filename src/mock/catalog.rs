@@ -0,0 +1,108 @@
+use serde_json as json;
+
+// Products, OS templates and snapshot plans served by the mock. The shapes
+// follow what the portal returns for `/products` and friends.
+
+pub(super) fn products() -> Vec<json::Value> {
+    vec![
+        json::json!({
+            "product_id": 1538,
+            "name": "NEO Lite Pro 1.1",
+            "description": "1 vCPU, 1 GB RAM, 20 GB SSD",
+            "category_id": 30,
+            "category_name": "NEO Lite Pro",
+            "options": { "type": "vm", "cores": 1, "memory": 1024, "allow_downgrade": 0 },
+            "billing": [
+                {
+                    "label": "Monthly",
+                    "cycle": "monthly",
+                    "price": 120000,
+                    "components": [{
+                        "label": "Additional Storage",
+                        "field": "disk_size",
+                        "prices": [
+                            { "qty_min": 0, "qty_max": 100, "price": 1500 },
+                            { "qty_min": 101, "qty_max": 500, "price": 1200 }
+                        ]
+                    }]
+                },
+                {
+                    "label": "Annually",
+                    "cycle": "annually",
+                    "price": 1296000,
+                    "components": [{
+                        "label": "Additional Storage",
+                        "field": "disk_size",
+                        "prices": [
+                            { "qty_min": 0, "qty_max": 100, "price": 16200 },
+                            { "qty_min": 101, "qty_max": 500, "price": 12960 }
+                        ]
+                    }]
+                }
+            ]
+        }),
+        json::json!({
+            "product_id": 1539,
+            "name": "NEO Lite Pro 2.2",
+            "description": "2 vCPU, 2 GB RAM, 40 GB SSD",
+            "category_id": 30,
+            "category_name": "NEO Lite Pro",
+            "options": { "type": "vm", "cores": 2, "memory": 2048, "allow_downgrade": 0 },
+            "billing": [
+                { "label": "Monthly", "cycle": "monthly", "price": 240000, "components": null },
+                { "label": "Quarterly", "cycle": "quarterly", "price": 684000, "components": null }
+            ]
+        }),
+        json::json!({
+            "product_id": 1540,
+            "name": "NEO Lite 4.8",
+            "description": "4 vCPU, 8 GB RAM, 80 GB SSD",
+            "category_id": 31,
+            "category_name": "NEO Lite",
+            "options": { "type": "vm", "cores": 4, "memory": 8192, "allow_downgrade": 1 },
+            "billing": [
+                { "label": "Monthly", "cycle": "monthly", "price": 600000, "components": null }
+            ]
+        }),
+    ]
+}
+
+pub(super) fn oses() -> Vec<json::Value> {
+    vec![
+        json::json!({
+            "vmid": 1001,
+            "node": "jkt-node-01",
+            "name": "ubuntu-22.04",
+            "maxmem": 8_589_934_592u64,
+            "maxcpu": 4
+        }),
+        json::json!({
+            "vmid": 1002,
+            "node": "jkt-node-01",
+            "name": "debian-12",
+            "maxmem": 8_589_934_592u64,
+            "maxcpu": 4
+        }),
+        json::json!({
+            "vmid": 1003,
+            "node": "jkt-node-02",
+            "name": "rockylinux-9",
+            "maxmem": 2_147_483_648u64,
+            "maxcpu": 2
+        }),
+    ]
+}
+
+pub(super) fn snapshot_products() -> Vec<json::Value> {
+    vec![json::json!({
+        "product_id": 2001,
+        "name": "NEO Lite Snapshot",
+        "description": "Snapshot of a NEO Lite virtual machine",
+        "category_id": 40,
+        "category_name": "NEO Lite Snapshot",
+        "options": { "type": "snapshot", "cores": 0, "memory": 0, "allow_downgrade": 0 },
+        "billing": [
+            { "label": "Monthly", "cycle": "monthly", "price": 15000, "components": null }
+        ]
+    })]
+}
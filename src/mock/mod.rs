@@ -0,0 +1,893 @@
+//! A stateful, in-process fake of the NEO Lite REST API.
+//!
+//! [`MockServer`] is a [`Transport`], so it plugs straight into a [`Client`]:
+//!
+//! ```
+//! # async fn run() -> Result<(), neolite::Error> {
+//! use neolite::{lite::Lite, mock::MockServer};
+//!
+//! let lite = Lite::new(MockServer::new().into_client());
+//! let keypair = lite.keypair().await?.create("gandalf0").await?;
+//! # Ok(())
+//! # }
+//! ```
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use http::{Method, StatusCode};
+use serde_json as json;
+use tokio::time::Instant;
+
+use crate::{
+    client::Client,
+    config::Config,
+    transport::{BoxFuture, Request, Response, Transport},
+};
+
+mod catalog;
+
+pub const BASE_URL: &str = "http://neolite.mock/v1/neolites";
+pub const TOKEN: &str = "mock-token";
+
+pub struct MockServer {
+    state: Mutex<State>,
+    faults: Mutex<Vec<FaultRule>>,
+    provisioning_delay: Duration,
+}
+
+impl fmt::Debug for MockServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockServer")
+            .field("provisioning_delay", &self.provisioning_delay)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Default for MockServer {
+    fn default() -> Self {
+        Self {
+            state: Mutex::new(State::new()),
+            faults: Mutex::new(Vec::new()),
+            provisioning_delay: Duration::ZERO,
+        }
+    }
+}
+
+impl MockServer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// How long a new VM or snapshot stays `Pending` before becoming `Active`.
+    pub fn with_provisioning_delay(mut self, delay: Duration) -> Self {
+        self.provisioning_delay = delay;
+        self
+    }
+    /// Configuration pointing at the mock.
+    pub fn config() -> Config {
+        Config::new(BASE_URL.parse().expect("valid mock URL"), TOKEN)
+    }
+    pub fn into_client(self) -> Client {
+        Client::with_transport(Self::config(), self)
+    }
+    /// A client sharing this mock, so the test can keep inspecting it.
+    pub fn client(self: &Arc<Self>) -> Client {
+        Client::with_transport(Self::config(), Arc::clone(self))
+    }
+    pub fn inject(&self, rule: FaultRule) {
+        self.faults.lock().expect("poisoned lock").push(rule);
+    }
+    pub fn clear_faults(&self) {
+        self.faults.lock().expect("poisoned lock").clear();
+    }
+    pub fn set_ip_available(&self, product_id: u32, available: bool) {
+        let mut state = self.state.lock().expect("poisoned lock");
+        state.ip_unavailable.retain(|id| *id != product_id);
+        if !available {
+            state.ip_unavailable.push(product_id);
+        }
+    }
+    /// Account ids of every VM the mock knows about, including terminated ones.
+    pub fn vm_ids(&self) -> Vec<u32> {
+        let state = self.state.lock().expect("poisoned lock");
+        state
+            .accounts
+            .values()
+            .filter(|account| account.kind == Kind::VirtualMachine)
+            .map(|account| account.id)
+            .collect()
+    }
+    /// Returns the faults matching this request, consuming one use of each.
+    fn take_faults(&self, method: &Method, path: &str) -> Vec<Fault> {
+        let mut rules = self.faults.lock().expect("poisoned lock");
+        let mut faults = Vec::new();
+        for rule in rules.iter_mut() {
+            if !rule.matches(method, path) {
+                continue;
+            }
+            if let Some(times) = rule.times.as_mut() {
+                *times -= 1;
+            }
+            faults.push(rule.fault.clone());
+        }
+        rules.retain(|rule| rule.times != Some(0));
+        faults
+    }
+    fn handle(&self, method: &Method, path: &str, query: &str, body: Option<json::Value>) -> Reply {
+        let mut state = self.state.lock().expect("poisoned lock");
+        state.refresh(self.provisioning_delay);
+
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        let body = Body(body);
+        match (method.clone(), segments.as_slice()) {
+            (Method::GET, ["accounts"]) => Ok(state.list_accounts(query)),
+            (Method::POST, []) => state.create_vm(&body),
+            (Method::DELETE, [id]) => state.terminate(Kind::VirtualMachine, parse_id(id)?),
+            (Method::GET, ["accounts", id]) => {
+                let account = state.account(Kind::VirtualMachine, parse_id(id)?)?;
+                Ok(state.account_json(account))
+            }
+            (Method::GET, ["accounts", id, "vm-details"]) => state.vm_details(parse_id(id)?),
+            (Method::PUT, ["accounts", id, "vm-state", power]) => {
+                state.change_power(parse_id(id)?, power)
+            }
+            (Method::PUT, ["accounts", id, "keypair"]) => {
+                let keypair_id = body.u32("keypair_id")?;
+                state.keypair(keypair_id)?;
+                state.vm_mut(parse_id(id)?)?.keypair_id = keypair_id;
+                Ok(json::Value::Null)
+            }
+            (Method::PUT, ["accounts", id, "change-vm-name"]) => {
+                let name = body.str("name")?;
+                state.vm_mut(parse_id(id)?)?.name = name;
+                Ok(json::Value::Null)
+            }
+            (Method::POST, ["accounts", id, "change-package"]) => {
+                let product_id = body.u32("new_product_id")?;
+                let cycle = body.str("cycle")?;
+                state.check_cycle(product_id, &cycle)?;
+                let account = state.vm_mut(parse_id(id)?)?;
+                account.product_id = product_id;
+                account.cycle = cycle;
+                let account_id = account.id;
+                Ok(state.order(account_id))
+            }
+            (Method::PUT, ["accounts", id, "storage"]) => {
+                let disk_size = body.u32("disk_size")?;
+                let account = state.vm_mut(parse_id(id)?)?;
+                account.disk_size = disk_size;
+                let account_id = account.id;
+                Ok(state.order(account_id))
+            }
+            (Method::PUT, ["accounts", id, "rebuild"]) => {
+                let os_name = body.str("name")?;
+                state.os(&os_name)?;
+                state.vm_mut(parse_id(id)?)?.os_name = os_name;
+                Ok(json::Value::Null)
+            }
+            (Method::POST, ["accounts", id, "snapshot"]) => {
+                state.create_snapshot(parse_id(id)?, &body)
+            }
+            (Method::GET, ["keypairs"]) => Ok(state.list_keypairs()),
+            (Method::POST, ["keypairs"]) => state.create_keypair(&body),
+            (Method::DELETE, ["keypairs", id]) => state.delete_keypair(parse_id(id)?),
+            (Method::GET, ["products"]) => Ok(json::Value::from(state.products.clone())),
+            (Method::GET, ["products", id]) => state.product(parse_id(id)?).cloned(),
+            (Method::GET, ["products", id, "oss"]) => {
+                state.product(parse_id(id)?)?;
+                Ok(json::Value::from(state.oses.clone()))
+            }
+            (Method::GET, ["products", id, "ip-availability"]) => {
+                let id = parse_id(id)?;
+                state.product(id)?;
+                let available = !state.ip_unavailable.contains(&id);
+                Ok(json::json!({ "available": available }))
+            }
+            (Method::GET, ["snapshots", "products"]) => {
+                Ok(json::Value::from(state.snapshot_products.clone()))
+            }
+            (Method::GET, ["snapshots", "products", id]) => {
+                state.snapshot_product(parse_id(id)?).cloned()
+            }
+            (Method::GET, ["snapshots", "accounts"]) => Ok(state.list_snapshots()),
+            (Method::GET, ["snapshots", "accounts", id]) => {
+                let snapshot = state.account(Kind::Snapshot, parse_id(id)?)?;
+                Ok(snapshot.to_snapshot_json())
+            }
+            (Method::DELETE, ["snapshots", id]) => state.terminate(Kind::Snapshot, parse_id(id)?),
+            (Method::PUT, ["snapshots", "accounts", id, "restore"]) => {
+                let snapshot = state.account(Kind::Snapshot, parse_id(id)?)?;
+                let vm_id = snapshot.source_id.ok_or_else(|| not_found("VM"))?;
+                state.vm_mut(vm_id)?.power = PowerState::Stopped;
+                Ok(json::Value::Null)
+            }
+            (Method::POST, ["snapshots", "accounts", id, "create"]) => {
+                let snapshot = state.account(Kind::Snapshot, parse_id(id)?)?;
+                let os_name = state.account(Kind::VirtualMachine, snapshot.source_id.unwrap_or(0));
+                let os_name = os_name.map(|vm| vm.os_name.clone()).unwrap_or_default();
+                let body = body.with("select_os", os_name.into());
+                state.create_vm(&body)
+            }
+            _ => Err((
+                StatusCode::NOT_FOUND,
+                format!("No route for {} {}", method, path),
+            )),
+        }
+    }
+}
+
+impl Transport for MockServer {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, crate::Error>> {
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            // Strip the base path so routes read like the ones in the API docs.
+            let path = parts.uri.path();
+            let base_path = BASE_URL
+                .parse::<http::Uri>()
+                .map(|uri| uri.path().to_string())
+                .unwrap_or_default();
+            let path = path.strip_prefix(base_path.as_str()).unwrap_or(path);
+            let query = parts.uri.query().unwrap_or_default();
+
+            let mut status = None;
+            for fault in self.take_faults(&parts.method, path) {
+                match fault {
+                    Fault::Latency(delay) => tokio::time::sleep(delay).await,
+                    Fault::Status(code) => status = status.or(Some(code)),
+                }
+            }
+
+            let token = parts.headers.get("x-token").and_then(|v| v.to_str().ok());
+            let reply = if let Some(code) = status {
+                Err((code, "Injected fault".to_string()))
+            } else if token != Some(TOKEN) {
+                Err((StatusCode::UNAUTHORIZED, "Invalid token".to_string()))
+            } else {
+                self.handle(&parts.method, path, query, body)
+            };
+
+            let (status, body) = match reply {
+                Ok(data) => {
+                    let code = StatusCode::OK;
+                    let body =
+                        json::json!({ "success": true, "code": code.as_u16(), "data": data });
+                    (code, body)
+                }
+                Err((code, message)) => {
+                    let body = json::json!({ "success": false, "code": code.as_u16(), "message": message });
+                    (code, body)
+                }
+            };
+            http::Response::builder()
+                .status(status)
+                .header("Content-Type", "application/json")
+                .body(body.to_string())
+                .map_err(|e| crate::Error::Internal(e.to_string()))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Fault {
+    /// Delays the response. Other faults and the regular handling still apply.
+    Latency(Duration),
+    /// Answers with this status instead of handling the request.
+    Status(StatusCode),
+}
+
+/// Where and how often to inject a [`Fault`].
+#[derive(Debug, Clone)]
+pub struct FaultRule {
+    fault: Fault,
+    method: Option<Method>,
+    path: Option<String>,
+    times: Option<u32>,
+}
+
+impl FaultRule {
+    /// A fault applied to every request, forever.
+    pub fn new(fault: Fault) -> Self {
+        Self {
+            fault,
+            method: None,
+            path: None,
+            times: None,
+        }
+    }
+    pub fn latency(delay: Duration) -> Self {
+        Self::new(Fault::Latency(delay))
+    }
+    pub fn status(status: StatusCode) -> Self {
+        Self::new(Fault::Status(status))
+    }
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = Some(method);
+        self
+    }
+    /// Only requests whose path starts with `prefix`, e.g. `/accounts/1001`.
+    pub fn path(mut self, prefix: &str) -> Self {
+        self.path = Some(prefix.to_string());
+        self
+    }
+    /// Only the next `times` matching requests.
+    pub fn times(mut self, times: u32) -> Self {
+        self.times = Some(times);
+        self
+    }
+    fn matches(&self, method: &Method, path: &str) -> bool {
+        let method_matches = self.method.as_ref().map_or(true, |m| m == method);
+        let path_matches = self
+            .path
+            .as_deref()
+            .map_or(true, |prefix| path.starts_with(prefix));
+        method_matches && path_matches && self.times != Some(0)
+    }
+}
+
+type Reply = Result<json::Value, (StatusCode, String)>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    VirtualMachine,
+    Snapshot,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AccountStatus {
+    Pending,
+    Active,
+    Suspended,
+    Terminated,
+}
+
+impl AccountStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "Pending",
+            Self::Active => "Active",
+            Self::Suspended => "Suspended",
+            Self::Terminated => "Terminated",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PowerState {
+    Running,
+    Stopped,
+    Paused,
+}
+
+impl PowerState {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Stopped => "stopped",
+            Self::Paused => "paused",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    products: Vec<json::Value>,
+    oses: Vec<json::Value>,
+    snapshot_products: Vec<json::Value>,
+    ip_unavailable: Vec<u32>,
+    keypairs: BTreeMap<u32, Keypair>,
+    accounts: BTreeMap<u32, Account>,
+    next_keypair_id: u32,
+    next_account_id: u32,
+    next_order_id: u32,
+}
+
+#[derive(Debug)]
+struct Keypair {
+    id: u32,
+    name: String,
+    public_key: String,
+}
+
+#[derive(Debug)]
+struct Account {
+    id: u32,
+    kind: Kind,
+    status: AccountStatus,
+    created: Instant,
+    name: String,
+    description: String,
+    product_id: u32,
+    cycle: String,
+    power: PowerState,
+    keypair_id: u32,
+    os_name: String,
+    disk_size: u32,
+    username: String,
+    password: String,
+    /// The VM a snapshot was taken from.
+    source_id: Option<u32>,
+}
+
+impl State {
+    fn new() -> Self {
+        Self {
+            products: catalog::products(),
+            oses: catalog::oses(),
+            snapshot_products: catalog::snapshot_products(),
+            ip_unavailable: Vec::new(),
+            keypairs: BTreeMap::new(),
+            accounts: BTreeMap::new(),
+            next_keypair_id: 1,
+            next_account_id: 1001,
+            next_order_id: 5001,
+        }
+    }
+    /// Moves accounts whose provisioning delay has passed to `Active`.
+    fn refresh(&mut self, delay: Duration) {
+        let now = Instant::now();
+        for account in self.accounts.values_mut() {
+            if account.status == AccountStatus::Pending && now >= account.created + delay {
+                account.status = AccountStatus::Active;
+                account.power = PowerState::Running;
+            }
+        }
+    }
+    fn product(&self, id: u32) -> Result<&json::Value, (StatusCode, String)> {
+        find_by_id(&self.products, "product_id", id).ok_or_else(|| not_found("Product"))
+    }
+    fn snapshot_product(&self, id: u32) -> Result<&json::Value, (StatusCode, String)> {
+        find_by_id(&self.snapshot_products, "product_id", id).ok_or_else(|| not_found("Product"))
+    }
+    fn os(&self, name: &str) -> Result<&json::Value, (StatusCode, String)> {
+        self.oses
+            .iter()
+            .find(|os| os["name"] == name)
+            .ok_or_else(|| bad_request(&format!("Unknown OS `{}`", name)))
+    }
+    fn keypair(&self, id: u32) -> Result<&Keypair, (StatusCode, String)> {
+        self.keypairs.get(&id).ok_or_else(|| not_found("Keypair"))
+    }
+    fn account(&self, kind: Kind, id: u32) -> Result<&Account, (StatusCode, String)> {
+        self.accounts
+            .get(&id)
+            .filter(|account| account.kind == kind)
+            .ok_or_else(|| not_found("Account"))
+    }
+    fn vm_mut(&mut self, id: u32) -> Result<&mut Account, (StatusCode, String)> {
+        self.accounts
+            .get_mut(&id)
+            .filter(|account| {
+                account.kind == Kind::VirtualMachine && account.status != AccountStatus::Terminated
+            })
+            .ok_or_else(|| not_found("VM"))
+    }
+    fn order(&mut self, account_id: u32) -> json::Value {
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        json::json!({ "order_id": order_id.to_string(), "account_id": account_id.to_string() })
+    }
+    fn new_account(&mut self, kind: Kind, body: &Body) -> Result<Account, (StatusCode, String)> {
+        let id = self.next_account_id;
+        self.next_account_id += 1;
+        Ok(Account {
+            id,
+            kind,
+            status: AccountStatus::Pending,
+            created: Instant::now(),
+            name: body.str_or("vm_name", "name")?,
+            description: body.optional_str("description"),
+            product_id: 0,
+            cycle: body.str("cycle")?,
+            power: PowerState::Stopped,
+            keypair_id: 0,
+            os_name: String::new(),
+            disk_size: 20,
+            username: String::new(),
+            password: String::new(),
+            source_id: None,
+        })
+    }
+    fn check_cycle(&self, product_id: u32, cycle: &str) -> Result<(), (StatusCode, String)> {
+        let product = self.product(product_id)?;
+        let has_cycle = product["billing"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|billing| billing["cycle"] == cycle);
+        if !has_cycle {
+            return Err(bad_request(&format!("Unknown billing cycle `{}`", cycle)));
+        }
+        Ok(())
+    }
+    fn create_vm(&mut self, body: &Body) -> Reply {
+        let product_id = body.u32("product_id")?;
+        let cycle = body.str("cycle")?;
+        self.check_cycle(product_id, &cycle)?;
+        if self.ip_unavailable.contains(&product_id) {
+            return Err(bad_request("IP is not available"));
+        }
+        let os_name = body.str("select_os")?;
+        self.os(&os_name)?;
+        let keypair_id = body.u32("keypair_id")?;
+        self.keypair(keypair_id)?;
+
+        let mut account = self.new_account(Kind::VirtualMachine, body)?;
+        account.product_id = product_id;
+        account.os_name = os_name;
+        account.keypair_id = keypair_id;
+        account.username = body.str("ssh_and_console_user")?;
+        account.password = body.str("console_password")?;
+        let account_id = account.id;
+        self.accounts.insert(account_id, account);
+        Ok(self.order(account_id))
+    }
+    fn create_snapshot(&mut self, vm_id: u32, body: &Body) -> Reply {
+        self.vm_mut(vm_id)?;
+        let mut account = self.new_account(Kind::Snapshot, body)?;
+        account.product_id = 2001;
+        account.source_id = Some(vm_id);
+        let account_id = account.id;
+        self.accounts.insert(account_id, account);
+        Ok(self.order(account_id))
+    }
+    fn terminate(&mut self, kind: Kind, id: u32) -> Reply {
+        self.account(kind, id)?;
+        if let Some(account) = self.accounts.get_mut(&id) {
+            account.status = AccountStatus::Terminated;
+            account.power = PowerState::Stopped;
+        }
+        Ok(json::Value::Null)
+    }
+    fn list_accounts(&self, query: &str) -> json::Value {
+        let status = query
+            .split('&')
+            .find_map(|pair| pair.strip_prefix("status="));
+        let accounts: Vec<json::Value> = self
+            .accounts
+            .values()
+            .filter(|account| account.kind == Kind::VirtualMachine)
+            .filter(|account| status.map_or(true, |s| account.status.as_str() == s))
+            .map(|account| self.account_json(account))
+            .collect();
+        json::Value::from(accounts)
+    }
+    fn list_snapshots(&self) -> json::Value {
+        let snapshots: Vec<json::Value> = self
+            .accounts
+            .values()
+            .filter(|account| account.kind == Kind::Snapshot)
+            .map(Account::to_snapshot_json)
+            .collect();
+        json::Value::from(snapshots)
+    }
+    fn vm_details(&self, id: u32) -> Reply {
+        let account = self.account(Kind::VirtualMachine, id)?;
+        if account.status != AccountStatus::Active && account.status != AccountStatus::Suspended {
+            return Err(not_found("VM"));
+        }
+        let options = &self.product(account.product_id)?["options"];
+        let memory = options["memory"].as_i64().unwrap_or_default() * 1024 * 1024;
+        let running = account.power == PowerState::Running;
+        Ok(json::json!({
+            "vmid": 100 + account.id,
+            "name": account.name,
+            "status": account.power.as_str(),
+            "uptime": if running { 3600 } else { 0 },
+            "maxdisk": i64::from(account.disk_size) * 1024 * 1024 * 1024,
+            "maxmem": memory,
+            "mem": if running { memory / 2 } else { 0 },
+            "cpus": options["cores"],
+        }))
+    }
+    fn change_power(&mut self, id: u32, power: &str) -> Reply {
+        let account = self.vm_mut(id)?;
+        if account.status == AccountStatus::Pending {
+            return Err(bad_request("VM is still being provisioned"));
+        }
+        account.power = match power {
+            "start" | "resume" | "reset" => PowerState::Running,
+            "stop" | "shutdown" => PowerState::Stopped,
+            "suspend" => PowerState::Paused,
+            _ => return Err(bad_request(&format!("Unknown state `{}`", power))),
+        };
+        Ok(json::Value::Null)
+    }
+    fn list_keypairs(&self) -> json::Value {
+        let keypairs: Vec<json::Value> = self.keypairs.values().map(Keypair::to_json).collect();
+        json::Value::from(keypairs)
+    }
+    fn create_keypair(&mut self, body: &Body) -> Reply {
+        let name = body.str("name")?;
+        if self.keypairs.values().any(|key| key.name == name) {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("Keypair `{}` already exists", name),
+            ));
+        }
+        let id = self.next_keypair_id;
+        self.next_keypair_id += 1;
+        let mut public_key = body.optional_str("public_key");
+        if public_key.is_empty() {
+            public_key = format!(
+                "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIMock{:08} {}",
+                id, name
+            );
+        }
+        let keypair = Keypair {
+            id,
+            name,
+            public_key,
+        };
+        let response = keypair.to_json();
+        self.keypairs.insert(id, keypair);
+        Ok(response)
+    }
+    fn delete_keypair(&mut self, id: u32) -> Reply {
+        self.keypairs
+            .remove(&id)
+            .ok_or_else(|| not_found("Keypair"))?;
+        Ok(json::Value::Null)
+    }
+    fn account_json(&self, account: &Account) -> json::Value {
+        let product = self.product(account.product_id).ok();
+        let field = |name: &str| product.map_or(json::Value::Null, |p| p[name].clone());
+        let recurring_amount = product
+            .and_then(|p| p["billing"].as_array())
+            .into_iter()
+            .flatten()
+            .find(|billing| billing["cycle"] == account.cycle.as_str())
+            .map_or(json::Value::from(0), |billing| billing["price"].clone());
+        let keypair = self.keypairs.get(&account.keypair_id);
+        json::json!({
+            "account_id": account.id.to_string(),
+            "domain": "",
+            "status": account.status.as_str(),
+            "billingcycle": capitalize(&account.cycle),
+            "date_created": "2024-01-01",
+            "next_due": "2024-02-01",
+            "recurring_amount": recurring_amount,
+            "extra_details": {
+                "region": "jkt",
+                "region_label": "Jakarta",
+                "description": account.description,
+                "name": account.name,
+                "tenant_id": null,
+                "ciuser": account.username,
+                "cipassword": account.password,
+                "neosshkey_id": account.keypair_id,
+                "sshkeys": keypair.map(|key| key.public_key.clone()).unwrap_or_default(),
+                "osname": account.os_name,
+                "disk_size": account.disk_size.to_string(),
+                // The portal doesn't document this name, the SDK doesn't rely on it.
+                "ip_address": format!("103.150.{}.{}", account.id / 256, account.id % 256),
+            },
+            "product_id": account.product_id,
+            "product_name": field("name"),
+            "description": account.description,
+            "category_id": field("category_id"),
+            "category_name": field("category_name"),
+            "last_invoice": {
+                "id": account.id + 9000,
+                "paid_id": 0,
+                "status": "Paid",
+                "date": "2024-01-01",
+                "duedate": "2024-01-01",
+                "paybefore": "2024-01-01",
+                "datepaid": "2024-01-01 10:00:00",
+                "invoice_type": "new",
+            },
+        })
+    }
+}
+
+impl Account {
+    fn to_snapshot_json(&self) -> json::Value {
+        json::json!({
+            "account_id": self.id,
+            "status": self.status.as_str(),
+            "extra_details": {
+                "name": self.name,
+                "description": self.description,
+                "region": "jkt",
+            },
+        })
+    }
+}
+
+impl Keypair {
+    fn to_json(&self) -> json::Value {
+        json::json!({
+            "keypair_id": self.id.to_string(),
+            "name": self.name,
+            "public_key": self.public_key,
+        })
+    }
+}
+
+/// Request body accessors answering `400` on missing fields.
+struct Body(Option<json::Value>);
+
+impl Body {
+    fn get(&self, key: &str) -> Option<&json::Value> {
+        self.0.as_ref()?.get(key).filter(|value| !value.is_null())
+    }
+    fn str(&self, key: &str) -> Result<String, (StatusCode, String)> {
+        match self.get(key) {
+            Some(json::Value::String(value)) => Ok(value.clone()),
+            Some(value) => Ok(value.to_string()),
+            None => Err(bad_request(&format!("Missing field `{}`", key))),
+        }
+    }
+    fn str_or(&self, key: &str, fallback: &str) -> Result<String, (StatusCode, String)> {
+        self.str(key).or_else(|_| self.str(fallback))
+    }
+    fn optional_str(&self, key: &str) -> String {
+        self.str(key).unwrap_or_default()
+    }
+    fn u32(&self, key: &str) -> Result<u32, (StatusCode, String)> {
+        self.str(key)?
+            .parse()
+            .map_err(|_| bad_request(&format!("Invalid field `{}`", key)))
+    }
+    fn with(&self, key: &str, value: json::Value) -> Body {
+        let mut body = self.0.clone().unwrap_or_else(|| json::json!({}));
+        body[key] = value;
+        Body(Some(body))
+    }
+}
+
+fn find_by_id<'a>(items: &'a [json::Value], key: &str, id: u32) -> Option<&'a json::Value> {
+    items.iter().find(|item| item[key] == id)
+}
+
+fn parse_id(id: &str) -> Result<u32, (StatusCode, String)> {
+    id.parse().map_err(|_| not_found("Resource"))
+}
+
+fn not_found(what: &str) -> (StatusCode, String) {
+    (StatusCode::NOT_FOUND, format!("{} not found", what))
+}
+
+fn bad_request(message: &str) -> (StatusCode, String) {
+    (StatusCode::BAD_REQUEST, message.to_string())
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        account::{self, Account},
+        keypair::Keypair,
+        request::CreateVmRequest,
+        retry::RetryPolicy,
+        vm::{self, VirtualMachine},
+    };
+
+    fn client(mock: &Arc<MockServer>) -> Arc<Client> {
+        Arc::new(mock.client().with_retry_policy(RetryPolicy::none()))
+    }
+
+    async fn order_vm(client: &Arc<Client>) -> u32 {
+        let keypair = Keypair::new(Arc::clone(client))
+            .create("gandalf0")
+            .await
+            .unwrap();
+        let request = CreateVmRequest::new("thorin-os2", "thethorin", "SpeakFriendAndEnter123")
+            .plan_id(1538)
+            .os_id(1001)
+            .keypair_id(keypair.id);
+        let order = VirtualMachine::new(Arc::clone(client))
+            .create_with(&request)
+            .await
+            .unwrap();
+        order.account_id.0
+    }
+
+    #[tokio::test]
+    async fn remembers_created_resources() {
+        let mock = Arc::new(MockServer::new());
+        let keypair = Keypair::new(client(&mock));
+        let created = keypair.create("gandalf0").await.unwrap();
+        assert_eq!(keypair.get(created.id).await.unwrap().name, "gandalf0");
+        keypair.delete(created.id).await.unwrap();
+        let error = keypair.get(created.id).await.unwrap_err();
+        assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn rejects_an_invalid_token() {
+        let config = Config::new(BASE_URL.parse().unwrap(), "stolen-token");
+        let client = Client::with_transport(config, MockServer::new());
+        let error = client.get("/keypairs").await.unwrap_err();
+        assert!(matches!(error, crate::Error::Unauthorized(_)));
+    }
+
+    #[tokio::test]
+    async fn injects_a_status_a_limited_number_of_times() {
+        let mock = Arc::new(MockServer::new());
+        mock.inject(FaultRule::status(StatusCode::SERVICE_UNAVAILABLE).times(2));
+        let client = client(&mock);
+        for _ in 0..2 {
+            let error = client.get("/keypairs").await.unwrap_err();
+            assert_eq!(error.status(), Some(StatusCode::SERVICE_UNAVAILABLE));
+        }
+        client.get("/keypairs").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn injects_faults_by_method_and_path_prefix() {
+        let mock = Arc::new(MockServer::new());
+        let not_found = StatusCode::NOT_FOUND;
+        mock.inject(
+            FaultRule::status(not_found)
+                .method(Method::GET)
+                .path("/products/1538"),
+        );
+        let client = client(&mock);
+
+        let error = client.get("/products/1538/oss").await.unwrap_err();
+        assert_eq!(error.status(), Some(not_found));
+        client.get("/products/1539/oss").await.unwrap();
+        client.get("/keypairs").await.unwrap();
+        client
+            .post("/keypairs", json::json!({ "name": "gandalf0" }))
+            .await
+            .unwrap();
+
+        mock.clear_faults();
+        client.get("/products/1538/oss").await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn injects_latency() {
+        let mock = Arc::new(MockServer::new());
+        mock.inject(FaultRule::latency(Duration::from_secs(5)).times(1));
+        let client = client(&mock);
+
+        let started = Instant::now();
+        client.get("/keypairs").await.unwrap();
+        assert!(started.elapsed() >= Duration::from_secs(5));
+
+        let started = Instant::now();
+        client.get("/keypairs").await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn provisions_vms_after_the_delay() {
+        let mock = Arc::new(MockServer::new().with_provisioning_delay(Duration::from_secs(30)));
+        let client = client(&mock);
+        let id = order_vm(&client).await;
+        assert_eq!(mock.vm_ids(), [id]);
+
+        let account = Account::new(Arc::clone(&client));
+        assert_eq!(
+            account.get(id).await.unwrap().status,
+            account::AccountStatus::Pending
+        );
+        tokio::time::advance(Duration::from_secs(29)).await;
+        assert_eq!(
+            account.get(id).await.unwrap().status,
+            account::AccountStatus::Pending
+        );
+        tokio::time::advance(Duration::from_secs(1)).await;
+        assert_eq!(
+            account.get(id).await.unwrap().status,
+            account::AccountStatus::Active
+        );
+
+        let vm = VirtualMachine::new(client).get(id).await.unwrap();
+        assert_eq!(vm.status, vm::PowerState::Running);
+    }
+}
@@ -0,0 +1,246 @@
+use std::{
+    fmt,
+    iter::Sum,
+    ops::{Add, AddAssign, Sub},
+};
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+
+/// The NEO Lite portal bills in Indonesian Rupiah only.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[non_exhaustive]
+pub enum Currency {
+    #[default]
+    Idr,
+}
+
+impl Currency {
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Idr => "IDR",
+        }
+    }
+}
+
+/// An amount of money in whole units of its currency.
+///
+/// It is (de)serialized as the bare number the API uses. `+`, `-` and
+/// [`Sum`] saturate at the bounds of `i64`; use [`checked_add`](Self::checked_add),
+/// [`checked_sub`](Self::checked_sub) and [`checked_mul`](Self::checked_mul)
+/// to detect an overflow instead.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Money {
+    pub amount: i64,
+    pub currency: Currency,
+}
+
+impl Money {
+    pub fn idr(amount: i64) -> Self {
+        Self {
+            amount,
+            currency: Currency::Idr,
+        }
+    }
+    pub fn zero() -> Self {
+        Self::default()
+    }
+    pub fn is_zero(&self) -> bool {
+        self.amount == 0
+    }
+    /// Splits the amount into `parts`, rounded to the nearest unit.
+    pub fn div_round(self, parts: i64) -> Self {
+        let amount = self.amount.saturating_add(parts / 2).div_euclid(parts);
+        Self { amount, ..self }
+    }
+    /// Adds the amounts, `None` on overflow.
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        debug_assert_eq!(self.currency, other.currency);
+        let amount = self.amount.checked_add(other.amount)?;
+        Some(Self { amount, ..self })
+    }
+    /// Subtracts the amounts, `None` on overflow.
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        debug_assert_eq!(self.currency, other.currency);
+        let amount = self.amount.checked_sub(other.amount)?;
+        Some(Self { amount, ..self })
+    }
+    /// Multiplies the amount, `None` on overflow.
+    pub fn checked_mul(self, factor: i64) -> Option<Self> {
+        let amount = self.amount.checked_mul(factor)?;
+        Some(Self { amount, ..self })
+    }
+}
+
+impl Add for Money {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        debug_assert_eq!(self.currency, other.currency);
+        Self {
+            amount: self.amount.saturating_add(other.amount),
+            ..self
+        }
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Money {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        debug_assert_eq!(self.currency, other.currency);
+        Self {
+            amount: self.amount.saturating_sub(other.amount),
+            ..self
+        }
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::zero(), Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Money> for Money {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+/// Prints `IDR 1.250.000`, grouping thousands with dots as Indonesians do.
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.amount.unsigned_abs().to_string();
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push('.');
+            }
+            grouped.push(digit);
+        }
+        let sign = if self.amount < 0 { "-" } else { "" };
+        write!(f, "{} {}{}", self.currency.code(), sign, grouped)
+    }
+}
+
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_i64(self.amount)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+struct MoneyVisitor;
+
+impl<'de> de::Visitor<'de> for MoneyVisitor {
+    type Value = Money;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an amount of money as a number or a numeric string")
+    }
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+        Ok(Money::idr(value))
+    }
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+        let value = i64::try_from(value).map_err(E::custom)?;
+        Ok(Money::idr(value))
+    }
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+        // Rupiah has no minor unit in practice. `240000.00` is `240000`.
+        Ok(Money::idr(value.round() as i64))
+    }
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(Money::zero());
+        }
+        if let Ok(amount) = value.parse::<i64>() {
+            return Ok(Money::idr(amount));
+        }
+        value
+            .parse::<f64>()
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+            .and_then(|amount| self.visit_f64(amount))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json as json;
+
+    use super::*;
+
+    fn parse(value: json::Value) -> Money {
+        json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn parses_numbers_and_numeric_strings() {
+        assert_eq!(parse(json::json!(240000)), Money::idr(240000));
+        assert_eq!(parse(json::json!(-1500)), Money::idr(-1500));
+        assert_eq!(parse(json::json!(240000.4)), Money::idr(240000));
+        assert_eq!(parse(json::json!("240000")), Money::idr(240000));
+        assert_eq!(parse(json::json!(" 240000.00 ")), Money::idr(240000));
+        assert_eq!(parse(json::json!("1500.50")), Money::idr(1501));
+        assert_eq!(parse(json::json!("")), Money::zero());
+    }
+
+    #[test]
+    fn rejects_non_numeric_values() {
+        assert!(json::from_value::<Money>(json::json!("free")).is_err());
+        assert!(json::from_value::<Money>(json::json!(u64::MAX)).is_err());
+        assert!(json::from_value::<Money>(json::json!(null)).is_err());
+    }
+
+    #[test]
+    fn displays_grouped_thousands() {
+        assert_eq!(Money::idr(0).to_string(), "IDR 0");
+        assert_eq!(Money::idr(999).to_string(), "IDR 999");
+        assert_eq!(Money::idr(1000).to_string(), "IDR 1.000");
+        assert_eq!(Money::idr(1250000).to_string(), "IDR 1.250.000");
+        assert_eq!(Money::idr(-45000).to_string(), "IDR -45.000");
+        assert_eq!(
+            Money::idr(i64::MIN).to_string(),
+            "IDR -9.223.372.036.854.775.808"
+        );
+    }
+
+    #[test]
+    fn divides_rounding_to_the_nearest_unit() {
+        assert_eq!(Money::idr(1296000).div_round(12), Money::idr(108000));
+        assert_eq!(Money::idr(10).div_round(3), Money::idr(3));
+        assert_eq!(Money::idr(11).div_round(3), Money::idr(4));
+        assert_eq!(Money::idr(-11).div_round(3), Money::idr(-4));
+    }
+
+    #[test]
+    fn saturates_or_checks_overflow() {
+        let max = Money::idr(i64::MAX);
+        let min = Money::idr(i64::MIN);
+        assert_eq!(max + Money::idr(1), max);
+        assert_eq!(min - Money::idr(1), min);
+        assert_eq!([max, max].iter().sum::<Money>(), max);
+        assert_eq!(max.checked_add(Money::idr(1)), None);
+        assert_eq!(min.checked_sub(Money::idr(1)), None);
+        assert_eq!(max.checked_mul(2), None);
+        assert_eq!(
+            Money::idr(2).checked_add(Money::idr(3)),
+            Some(Money::idr(5))
+        );
+        assert_eq!(
+            Money::idr(2).checked_sub(Money::idr(3)),
+            Some(Money::idr(-1))
+        );
+    }
+}
@@ -0,0 +1,47 @@
+use serde_json as json;
+
+pub(crate) const REDACTED: &str = "[REDACTED]";
+
+// Headers and JSON fields that carry credentials.
+const SECRET_HEADERS: &[&str] = &["x-token", "authorization"];
+const SECRET_FIELDS: &[&str] = &[
+    "console_password",
+    "cipassword",
+    "password",
+    "token",
+    "private_key",
+];
+
+pub(crate) fn is_secret_header(name: &str) -> bool {
+    SECRET_HEADERS
+        .iter()
+        .any(|secret| secret.eq_ignore_ascii_case(name))
+}
+
+/// Replaces the value of every secret field, at any depth.
+pub(crate) fn json_value(value: &mut json::Value) {
+    match value {
+        json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if SECRET_FIELDS.contains(&key.as_str()) && !value.is_null() {
+                    *value = json::Value::String(REDACTED.to_string());
+                } else {
+                    json_value(value);
+                }
+            }
+        }
+        json::Value::Array(values) => values.iter_mut().for_each(json_value),
+        _ => {}
+    }
+}
+
+/// Redacts a raw body. Bodies that are not JSON are returned unchanged.
+pub(crate) fn body(text: &str) -> String {
+    match json::from_str::<json::Value>(text) {
+        Ok(mut value) => {
+            json_value(&mut value);
+            value.to_string()
+        }
+        Err(_) => text.to_string(),
+    }
+}
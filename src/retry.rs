@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use reqwest::{header::HeaderMap, Method};
+
+/// Controls how [`Client`](crate::client::Client) retries failed requests.
+///
+/// By default only requests that are safe to repeat are retried. `POST`
+/// requests and the `PUT` requests that order storage, reset or rebuild
+/// a VM, or restore a snapshot, are sent once unless
+/// [`retry_non_idempotent`](Self::retry_non_idempotent) is set, since a retry
+/// after a timeout could run them twice.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+    retry_non_idempotent: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            retry_non_idempotent: false,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// A policy that sends every request exactly once.
+    pub fn none() -> Self {
+        Self::default().max_attempts(1)
+    }
+    /// Total number of attempts, including the first one.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+    pub fn base_delay(mut self, delay: Duration) -> Self {
+        self.base_delay = delay;
+        self
+    }
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+    /// Also retry `POST` and `PATCH` requests and the `PUT` requests that
+    /// place orders or restore snapshots.
+    pub fn retry_non_idempotent(mut self, retry: bool) -> Self {
+        self.retry_non_idempotent = retry;
+        self
+    }
+
+    /// Whether another attempt is allowed after `attempt` failed.
+    pub(crate) fn can_retry(&self, idempotent: bool, attempt: u32) -> bool {
+        attempt < self.max_attempts && (idempotent || self.retry_non_idempotent)
+    }
+    /// Delay before the next attempt, `attempt` being the one that just failed.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay);
+        if self.jitter {
+            // Full jitter: pick uniformly between zero and the computed delay.
+            delay.mul_f64(fastrand::f64())
+        } else {
+            delay
+        }
+    }
+    /// Delay before retrying `error`, or `None` if the server asked for
+    /// a longer wait than `max_delay`.
+    pub(crate) fn delay(&self, attempt: u32, error: &crate::Error) -> Option<Duration> {
+        match error.retry_after() {
+            Some(delay) if delay > self.max_delay => None,
+            Some(delay) => Some(delay),
+            None => Some(self.backoff(attempt)),
+        }
+    }
+}
+
+pub(crate) fn is_idempotent(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::PUT | Method::DELETE | Method::OPTIONS
+    )
+}
+
+// NOTE: Only the `delay-seconds` form is supported. The portal doesn't send HTTP dates.
+pub(crate) fn parse_retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    let seconds: u64 = value.trim().parse().ok()?;
+    Some(Duration::from_secs(seconds))
+}
@@ -0,0 +1,269 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use http::{HeaderMap, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json as json;
+
+use super::{BoxFuture, Request, Response, Transport};
+use crate::redact;
+
+/// Request/response pairs captured by [`Recorder`] and served by [`Player`].
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: CassetteRequest,
+    pub response: CassetteResponse,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteRequest {
+    pub method: String,
+    /// Path and query of the request URL.
+    pub path: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: Option<json::Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteResponse {
+    pub status: u16,
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path).map_err(|e| {
+            crate::Error::InvalidArgument(format!(
+                "Failed to read cassette `{}`: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Ok(json::from_str(&text)?)
+    }
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), crate::Error> {
+        let path = path.as_ref();
+        let text = json::to_string_pretty(self)?;
+        fs::write(path, text).map_err(|e| {
+            crate::Error::Internal(format!(
+                "Failed to write cassette `{}`: {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+}
+
+/// Forwards requests to another transport and writes every exchange to a
+/// cassette file. Tokens and passwords are redacted before they hit the disk.
+#[derive(Debug)]
+pub struct Recorder<T> {
+    inner: T,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+}
+
+impl<T: Transport> Recorder<T> {
+    /// Starts an empty cassette at `path`, replacing any existing file.
+    pub fn new(inner: T, path: impl Into<PathBuf>) -> Self {
+        Self {
+            inner,
+            path: path.into(),
+            cassette: Mutex::new(Cassette::default()),
+        }
+    }
+}
+
+impl<T: Transport> Transport for Recorder<T> {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, crate::Error>> {
+        Box::pin(async move {
+            let recorded = CassetteRequest::from_request(&request);
+            let response = self.inner.send(request).await?;
+            let interaction = Interaction {
+                request: recorded,
+                response: CassetteResponse::from_response(&response),
+            };
+
+            let mut cassette = self.cassette.lock().expect("poisoned lock");
+            cassette.interactions.push(interaction);
+            // Saved after every exchange so an aborted run still leaves a usable cassette.
+            cassette.save(&self.path)?;
+            Ok(response)
+        })
+    }
+}
+
+/// Serves the responses of a cassette and fails on requests it doesn't contain.
+///
+/// Each interaction is played once. Requests are matched on method, path and
+/// redacted body, in recording order, so concurrent calls replay fine.
+#[derive(Debug)]
+pub struct Player {
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl Player {
+    pub fn new(cassette: Cassette) -> Self {
+        let interactions = cassette.interactions.into_iter().map(Some).collect();
+        Self {
+            interactions: Mutex::new(interactions),
+        }
+    }
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, crate::Error> {
+        Ok(Self::new(Cassette::load(path)?))
+    }
+    /// Number of recorded interactions that were not requested.
+    pub fn remaining(&self) -> usize {
+        let interactions = self.interactions.lock().expect("poisoned lock");
+        interactions.iter().flatten().count()
+    }
+}
+
+impl Transport for Player {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, crate::Error>> {
+        Box::pin(async move {
+            let wanted = CassetteRequest::from_request(&request);
+            let mut interactions = self.interactions.lock().expect("poisoned lock");
+            let interaction = interactions
+                .iter_mut()
+                .find(|slot| {
+                    slot.as_ref().is_some_and(|interaction| {
+                        interaction.request.method == wanted.method
+                            && interaction.request.path == wanted.path
+                            && interaction.request.body == wanted.body
+                    })
+                })
+                .and_then(Option::take)
+                .ok_or_else(|| {
+                    crate::Error::Internal(format!(
+                        "Unexpected request {} {} with body {}. It is not in the cassette",
+                        wanted.method,
+                        wanted.path,
+                        wanted.body.map(|body| body.to_string()).unwrap_or_default()
+                    ))
+                })?;
+            interaction.response.into_response()
+        })
+    }
+}
+
+impl CassetteRequest {
+    fn from_request(request: &Request) -> Self {
+        let mut body = request.body().clone();
+        if let Some(body) = body.as_mut() {
+            redact::json_value(body);
+        }
+        Self {
+            method: request.method().to_string(),
+            path: request
+                .uri()
+                .path_and_query()
+                .map(|p| p.as_str().to_string())
+                .unwrap_or_default(),
+            headers: headers_to_map(request.headers()),
+            body,
+        }
+    }
+}
+
+impl CassetteResponse {
+    fn from_response(response: &Response) -> Self {
+        Self {
+            status: response.status().as_u16(),
+            headers: headers_to_map(response.headers()),
+            body: redact::body(response.body()),
+        }
+    }
+    fn into_response(self) -> Result<Response, crate::Error> {
+        let status = StatusCode::from_u16(self.status)
+            .map_err(|e| crate::Error::InvalidArgument(format!("Invalid cassette: {}", e)))?;
+        let mut builder = http::Response::builder().status(status);
+        for (name, value) in &self.headers {
+            builder = builder.header(name, value);
+        }
+        builder
+            .body(self.body)
+            .map_err(|e| crate::Error::InvalidArgument(format!("Invalid cassette: {}", e)))
+    }
+}
+
+fn headers_to_map(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if redact::is_secret_header(name.as_str()) {
+                redact::REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use http::Method;
+
+    use super::*;
+
+    fn request(body: json::Value) -> Request {
+        http::Request::builder()
+            .method(Method::POST)
+            .uri("http://neolite.test/keypairs")
+            .header("x-token", "secret-token")
+            .body(Some(body))
+            .unwrap()
+    }
+
+    fn cassette(body: json::Value) -> Cassette {
+        let response = CassetteResponse {
+            status: 200,
+            headers: BTreeMap::new(),
+            body: r#"{"success": true, "data": {}}"#.to_string(),
+        };
+        Cassette {
+            interactions: vec![Interaction {
+                request: CassetteRequest::from_request(&request(body)),
+                response,
+            }],
+        }
+    }
+
+    #[test]
+    fn redacts_recorded_requests() {
+        let body = json::json!({ "name": "gandalf0", "password": "SpeakFriend1" });
+        let recorded = CassetteRequest::from_request(&request(body));
+        assert_eq!(recorded.headers["x-token"], redact::REDACTED);
+        assert_eq!(recorded.body.unwrap()["password"], redact::REDACTED);
+    }
+
+    #[tokio::test]
+    async fn matches_the_redacted_body() {
+        let player = Player::new(cassette(json::json!({ "name": "gandalf0" })));
+        let error = player
+            .send(request(json::json!({ "name": "gandalf1" })))
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("gandalf1"));
+        assert_eq!(player.remaining(), 1);
+
+        let player = Player::new(cassette(json::json!({ "password": "SpeakFriend1" })));
+        player
+            .send(request(json::json!({ "password": "SpeakFriend2" })))
+            .await
+            .unwrap();
+        assert_eq!(player.remaining(), 0);
+    }
+}
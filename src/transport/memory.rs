@@ -0,0 +1,109 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Mutex,
+};
+
+use http::{HeaderMap, Method, StatusCode};
+use serde_json as json;
+
+use super::{BoxFuture, Request, Response, Transport};
+
+/// An in-memory [`Transport`] serving canned responses.
+///
+/// Responses are registered per method and path (including the query string),
+/// matched against the request URL. Several responses for the same route are
+/// served in order, and the last one keeps being served afterwards.
+#[derive(Debug, Default)]
+pub struct MemoryTransport {
+    routes: Mutex<HashMap<Route, VecDeque<Canned>>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+type Route = (Method, String);
+type Canned = (StatusCode, HeaderMap, String);
+
+/// A request received by [`MemoryTransport`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: Method,
+    pub path: String,
+    pub body: Option<json::Value>,
+}
+
+impl MemoryTransport {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Queues a response with a raw body.
+    pub fn push(&self, method: Method, path: &str, status: StatusCode, body: impl Into<String>) {
+        self.push_with_headers(method, path, status, HeaderMap::new(), body);
+    }
+    /// Queues a response with a raw body and headers, such as `Retry-After`.
+    pub fn push_with_headers(
+        &self,
+        method: Method,
+        path: &str,
+        status: StatusCode,
+        headers: HeaderMap,
+        body: impl Into<String>,
+    ) {
+        let mut routes = self.routes.lock().expect("poisoned lock");
+        routes
+            .entry((method, path.to_string()))
+            .or_default()
+            .push_back((status, headers, body.into()));
+    }
+    /// Queues a successful response wrapping `data` in the API envelope.
+    pub fn push_data(&self, method: Method, path: &str, data: json::Value) {
+        let body = json::json!({ "success": true, "code": 200, "data": data });
+        self.push(method, path, StatusCode::OK, body.to_string());
+    }
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().expect("poisoned lock").clone()
+    }
+    fn next_response(&self, method: &Method, path: &str) -> Option<Canned> {
+        let mut routes = self.routes.lock().expect("poisoned lock");
+        let queue = routes.get_mut(&(method.clone(), path.to_string()))?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+}
+
+impl Transport for MemoryTransport {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, crate::Error>> {
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let path = parts
+                .uri
+                .path_and_query()
+                .map(|p| p.as_str().to_string())
+                .unwrap_or_default();
+            self.requests
+                .lock()
+                .expect("poisoned lock")
+                .push(RecordedRequest {
+                    method: parts.method.clone(),
+                    path: path.clone(),
+                    body,
+                });
+
+            let (status, headers, body) =
+                self.next_response(&parts.method, &path).ok_or_else(|| {
+                    crate::Error::Internal(format!(
+                        "No response registered for {} {}",
+                        parts.method, path
+                    ))
+                })?;
+            let mut response = http::Response::builder()
+                .status(status)
+                .body(body)
+                .map_err(|e| crate::Error::Internal(e.to_string()))?;
+            *response.headers_mut() = headers;
+            Ok(response)
+        })
+    }
+}
@@ -0,0 +1,68 @@
+use std::{fmt, future::Future, pin::Pin, sync::Arc};
+
+use serde_json as json;
+
+mod cassette;
+mod memory;
+
+pub use cassette::{Cassette, CassetteRequest, CassetteResponse, Interaction, Player, Recorder};
+pub use memory::{MemoryTransport, RecordedRequest};
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// A request as handed to a [`Transport`]. The body is the JSON payload, if any.
+pub type Request = http::Request<Option<json::Value>>;
+
+/// A response as returned by a [`Transport`]. The body is the raw response text.
+pub type Response = http::Response<String>;
+
+/// Sends requests built by [`Client`](crate::client::Client).
+///
+/// Implement this to route the SDK somewhere other than the real portal.
+/// Transports only move bytes; status handling, the response envelope
+/// and retries stay in the client.
+pub trait Transport: fmt::Debug + Send + Sync {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, crate::Error>>;
+}
+
+impl<T: Transport + ?Sized> Transport for Arc<T> {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, crate::Error>> {
+        self.as_ref().send(request)
+    }
+}
+
+/// The default transport, backed by [`reqwest`].
+#[derive(Debug, Default, Clone)]
+pub struct ReqwestTransport {
+    client: reqwest::Client,
+}
+
+impl ReqwestTransport {
+    pub fn new(client: reqwest::Client) -> Self {
+        Self { client }
+    }
+}
+
+impl Transport for ReqwestTransport {
+    fn send(&self, request: Request) -> BoxFuture<'_, Result<Response, crate::Error>> {
+        Box::pin(async move {
+            let (parts, body) = request.into_parts();
+            let response = self
+                .client
+                .request(parts.method, parts.uri.to_string())
+                .headers(parts.headers)
+                .json(&body)
+                .send()
+                .await?;
+
+            let mut builder = http::Response::builder().status(response.status());
+            if let Some(headers) = builder.headers_mut() {
+                headers.extend(response.headers().clone());
+            }
+            let text = response.text().await?;
+            builder
+                .body(text)
+                .map_err(|e| crate::Error::Internal(e.to_string()))
+        })
+    }
+}
@@ -0,0 +1,195 @@
+use std::{future::Future, time::Duration};
+
+use tokio::time::Instant;
+
+/// Controls how long and how often a resource is polled while waiting for it
+/// to reach a state.
+///
+/// The interval grows by `backoff` after every poll, up to `max_interval`.
+#[derive(Debug, Clone)]
+pub struct WaitOptions {
+    timeout: Duration,
+    interval: Duration,
+    max_interval: Duration,
+    backoff: f64,
+}
+
+impl Default for WaitOptions {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(300),
+            interval: Duration::from_secs(2),
+            max_interval: Duration::from_secs(15),
+            backoff: 1.5,
+        }
+    }
+}
+
+impl WaitOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+    /// Delay before the second poll.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+    pub fn max_interval(mut self, interval: Duration) -> Self {
+        self.max_interval = interval;
+        self
+    }
+    /// Factor applied to the interval after every poll. `1.0` polls at a
+    /// fixed rate.
+    pub fn backoff(mut self, backoff: f64) -> Self {
+        self.backoff = backoff.max(1.0);
+        self
+    }
+    /// `interval` grown by `backoff` and clamped to `max_interval`, without
+    /// overflowing on a large factor.
+    fn next_interval(&self, interval: Duration) -> Duration {
+        let next = interval.as_secs_f64() * self.backoff;
+        Duration::try_from_secs_f64(next)
+            .unwrap_or(self.max_interval)
+            .min(self.max_interval)
+    }
+}
+
+/// Calls `fetch` until `done` accepts its result or the timeout elapses.
+///
+/// `fetch` returns `Ok(None)` while the resource doesn't exist yet.
+/// `describe` names the state carried by [`Error::WaitTimeout`](crate::Error::WaitTimeout).
+pub(crate) async fn poll<T, F, Fut>(
+    resource: String,
+    opts: &WaitOptions,
+    mut fetch: F,
+    mut done: impl FnMut(&T) -> bool,
+    describe: impl Fn(&T) -> String,
+) -> Result<T, crate::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<Option<T>, crate::Error>>,
+{
+    let started = Instant::now();
+    let mut interval = opts.interval;
+    loop {
+        let last_state = match fetch().await? {
+            Some(value) if done(&value) => return Ok(value),
+            Some(value) => describe(&value),
+            None => "not found".to_string(),
+        };
+
+        let elapsed = started.elapsed();
+        let Some(remaining) = opts.timeout.checked_sub(elapsed).filter(|r| !r.is_zero()) else {
+            return Err(crate::Error::WaitTimeout {
+                resource,
+                elapsed,
+                last_state,
+            });
+        };
+        tracing::debug!(
+            "{} is {}, polling again in {:?}",
+            resource,
+            last_state,
+            interval
+        );
+        tokio::time::sleep(interval.min(remaining)).await;
+        interval = opts.next_interval(interval);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+
+    /// Polls a resource that never becomes ready, returning the poll times
+    /// and the error.
+    async fn poll_forever(
+        opts: &WaitOptions,
+        state: Option<&str>,
+    ) -> (Vec<Duration>, crate::Error) {
+        let started = Instant::now();
+        let polls = Arc::new(Mutex::new(Vec::new()));
+        let result = poll(
+            "vm 1".to_string(),
+            opts,
+            || {
+                polls.lock().unwrap().push(started.elapsed());
+                let state = state.map(str::to_string);
+                async move { Ok(state) }
+            },
+            |_| false,
+            |state: &String| state.clone(),
+        )
+        .await;
+        let polls = polls.lock().unwrap().clone();
+        (polls, result.unwrap_err())
+    }
+
+    fn secs(secs: &[u64]) -> Vec<Duration> {
+        secs.iter().copied().map(Duration::from_secs).collect()
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn times_out_with_the_last_state() {
+        let opts = WaitOptions::new()
+            .timeout(Duration::from_secs(10))
+            .interval(Duration::from_secs(2))
+            .backoff(1.0);
+        let (polls, error) = poll_forever(&opts, Some("Building")).await;
+
+        assert_eq!(polls, secs(&[0, 2, 4, 6, 8, 10]));
+        let crate::Error::WaitTimeout {
+            resource,
+            elapsed,
+            last_state,
+        } = error
+        else {
+            panic!("unexpected error: {:?}", error);
+        };
+        assert_eq!(resource, "vm 1");
+        assert_eq!(elapsed, Duration::from_secs(10));
+        assert_eq!(last_state, "Building");
+
+        let (_, error) = poll_forever(&opts, None).await;
+        assert!(matches!(
+            error,
+            crate::Error::WaitTimeout { last_state, .. } if last_state == "not found"
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn backs_off_up_to_the_max_interval() {
+        let opts = WaitOptions::new()
+            .timeout(Duration::from_secs(30))
+            .interval(Duration::from_secs(1))
+            .max_interval(Duration::from_secs(5))
+            .backoff(2.0);
+        let (polls, _) = poll_forever(&opts, Some("Building")).await;
+
+        // Waits 1, 2, 4, then 5 seconds, and never sleeps past the timeout.
+        assert_eq!(polls, secs(&[0, 1, 3, 7, 12, 17, 22, 27, 30]));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clamps_a_huge_backoff() {
+        let opts = WaitOptions::new()
+            .timeout(Duration::from_secs(25))
+            .interval(Duration::from_secs(1))
+            .max_interval(Duration::from_secs(10))
+            .backoff(1e300);
+        let (polls, _) = poll_forever(&opts, Some("Building")).await;
+        assert_eq!(polls, secs(&[0, 1, 11, 21, 25]));
+
+        let opts = opts.backoff(f64::INFINITY);
+        assert_eq!(
+            opts.next_interval(Duration::from_secs(1)),
+            Duration::from_secs(10)
+        );
+    }
+}
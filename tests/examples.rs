@@ -0,0 +1,233 @@
+//! The flows of `examples/`, replayed from the cassettes in `tests/cassettes`.
+//!
+//! The cassettes were recorded against the bundled mock. To record them again:
+//!
+//! ```sh
+//! NEOLITE_RECORD=1 cargo test --features mock --test examples
+//! ```
+use std::{path::PathBuf, sync::Arc};
+
+use neolite::{
+    client::Client,
+    config::Config,
+    lite::{BillingResource, Lite},
+    plan::{BillingCycle, VmRequirements},
+    snapshot::SnapshotOpts,
+    transport::Player,
+    vm::{PowerState, VirtualMachineOptions},
+    wait::WaitOptions,
+};
+
+const PUBLIC_KEY: &str =
+    "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGpO6QmZtQZ5kEZ8QtKuMpMX2Pe9Pw5nA8c2dHtMEmWa gandalf@shire";
+
+/// A client for one test, replaying `tests/cassettes/{name}.json`.
+struct Session {
+    lite: Lite,
+    player: Option<Arc<Player>>,
+}
+
+impl Session {
+    fn new(name: &str) -> Self {
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/cassettes")
+            .join(format!("{}.json", name));
+
+        #[cfg(feature = "mock")]
+        if std::env::var_os("NEOLITE_RECORD").is_some() {
+            use neolite::mock::MockServer;
+
+            let client = Client::builder(MockServer::config())
+                .transport(MockServer::new())
+                .record(path)
+                .build()
+                .expect("recording client");
+            return Self {
+                lite: Lite::new(client),
+                player: None,
+            };
+        }
+
+        let player = Arc::new(Player::from_file(&path).expect("cassette"));
+        let config = Config::new("http://neolite.test/v1/neolites".parse().unwrap(), "token");
+        Self {
+            lite: Lite::new(Client::with_transport(config, Arc::clone(&player))),
+            player: Some(player),
+        }
+    }
+    /// Checks that the test sent every recorded request.
+    fn finish(self) {
+        if let Some(player) = self.player {
+            assert_eq!(player.remaining(), 0, "requests left in the cassette");
+        }
+    }
+}
+
+async fn create_vm(lite: &Lite) -> anyhow::Result<BillingResource> {
+    let plan = lite.plan().await?;
+    let requirements = VmRequirements::new(BillingCycle::Monthly)
+        .min_cores(1)
+        .min_memory(1024)
+        .ip_available(true);
+    let plan_resource = plan
+        .find_vm(&requirements)
+        .await?
+        .into_iter()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("No plan meets the requirements"))?;
+    let billing_resource = plan_resource.get_billing(BillingCycle::Monthly).await?;
+    assert!(plan_resource.ip().await?.is_available().await?);
+    let os_resource = plan_resource.os().await?.get(1001).await?;
+    let keypair_resource = lite.keypair().await?.create("gandalf0").await?;
+
+    let opts = VirtualMachineOptions {
+        plan: plan_resource,
+        os: os_resource,
+        keypair: keypair_resource,
+        cycle: billing_resource.cycle,
+        use_credit_card: false,
+        promocode: None,
+    };
+    let order = lite
+        .vm()
+        .await?
+        .create(
+            "thorin-os2".to_string(),
+            Some("Thorin Virtual Machine".to_string()),
+            "thethorin".to_string(),
+            "SpeakFriendAndEnter123".to_string(),
+            &opts,
+        )
+        .await?;
+    Ok(order)
+}
+
+#[tokio::test]
+async fn keypair() -> anyhow::Result<()> {
+    let session = Session::new("keypair");
+    let keypair = session.lite.keypair().await?;
+
+    let created = keypair.create("gandalf0").await?;
+    let imported = keypair.import("gandalf1", PUBLIC_KEY).await?;
+    assert_eq!(keypair.get(created.id).await?.name, "gandalf0");
+    assert_eq!(keypair.list().await?.len(), 2);
+    keypair.delete(imported.id).await?;
+    assert_eq!(keypair.list().await?.len(), 1);
+
+    session.finish();
+    Ok(())
+}
+
+#[tokio::test]
+async fn plan() -> anyhow::Result<()> {
+    let session = Session::new("plan");
+    let plan = session.lite.plan().await?;
+
+    assert!(!plan.list_vm().await?.is_empty());
+    let plan_resource = plan.get_vm(1538).await?;
+    let os = plan_resource.os().await?;
+    assert!(!os.list().await?.is_empty());
+    assert_eq!(os.get(1001).await?.id, 1001);
+    assert!(plan_resource.ip().await?.is_available().await?);
+
+    let snapshot_plans = plan.list_snapshot().await?;
+    let snapshot_plan = plan.get_snapshot(snapshot_plans[0].id).await?;
+    assert_eq!(snapshot_plan.id, snapshot_plans[0].id);
+
+    session.finish();
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_vm_and_wait() -> anyhow::Result<()> {
+    let session = Session::new("create_vm");
+
+    let order = create_vm(&session.lite).await?;
+    let vm = order.wait_for_vm(&WaitOptions::default()).await?;
+    assert_eq!(vm.name, "thorin-os2");
+    assert_eq!(vm.status, PowerState::Running);
+
+    session.finish();
+    Ok(())
+}
+
+#[tokio::test]
+async fn vm() -> anyhow::Result<()> {
+    let session = Session::new("vm");
+    let order = create_vm(&session.lite).await?;
+    let created = order.wait_for_vm(&WaitOptions::default()).await?;
+
+    let vm = session.lite.vm().await?;
+    assert_eq!(vm.list().await?.len(), 1);
+    let resource = vm.get(created.id).await?;
+    resource.stop().await?;
+    let stopped = resource
+        .wait_for_state(PowerState::Stopped, &WaitOptions::default())
+        .await?;
+    assert_eq!(stopped.status, PowerState::Stopped);
+    vm.delete(created.id).await?;
+
+    session.finish();
+    Ok(())
+}
+
+#[tokio::test]
+async fn account() -> anyhow::Result<()> {
+    let session = Session::new("account");
+    let order = create_vm(&session.lite).await?;
+
+    let account = session.lite.account().await?;
+    assert_eq!(account.list().await?.len(), 1);
+    let resource = account.get(order.account_id.into()).await?;
+    assert_eq!(resource.id, u32::from(order.account_id));
+    let error = account.get(9999).await.unwrap_err();
+    assert_eq!(error.to_string(), "Account not found");
+    assert!(error.api_error().is_some());
+
+    session.finish();
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_snapshot() -> anyhow::Result<()> {
+    let session = Session::new("create_snapshot");
+    let order = create_vm(&session.lite).await?;
+    let vm = order.wait_for_vm(&WaitOptions::default()).await?;
+
+    let plan_resource = session.lite.plan().await?.get_vm(1538).await?;
+    let billing_resource = plan_resource.get_billing(BillingCycle::Monthly).await?;
+    let opts = SnapshotOpts {
+        cycle: billing_resource.cycle,
+        use_credit_card: false,
+        promocode: None,
+    };
+    let snapshot = session.lite.snapshot().await?;
+    let order = snapshot
+        .create(
+            vm.id,
+            "snapshot-from-sdk".to_string(),
+            Some("Snapshot from SDK".to_string()),
+            &opts,
+        )
+        .await?;
+    let created = order.wait_for_snapshot(&WaitOptions::default()).await?;
+    assert_eq!(snapshot.get(created.id).await?.id, created.id);
+    assert_eq!(snapshot.list().await?.len(), 1);
+
+    session.finish();
+    Ok(())
+}
+
+#[tokio::test]
+async fn report() -> anyhow::Result<()> {
+    let session = Session::new("report");
+    let order = create_vm(&session.lite).await?;
+    order.wait_for_vm(&WaitOptions::default()).await?;
+
+    let cost = session.lite.report().await?.cost().await?;
+    assert_eq!(cost.by_product.len(), 1);
+    assert_eq!(cost.total, cost.by_product.values().copied().sum());
+
+    session.finish();
+    Ok(())
+}